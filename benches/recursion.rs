@@ -0,0 +1,27 @@
+// Deep recursion benchmark
+//
+// It times a recursive fibonacci, where every call enters a function and a
+// few blocks, on both backends. Run it with `cargo bench`
+extern crate rascal;
+
+use std::time::Instant;
+use rascal::{Interpreter, Backend};
+
+const FIB: &str = "
+let fib = fn [n] { if n < 2 { n else fib(n - 1) + fib(n - 2) } };
+fib(22)
+";
+
+fn bench(name: &str, backend: Backend) {
+    let runs = 5;
+    let start = Instant::now();
+    for _ in 0..runs {
+        Interpreter::with_backend(backend).eval(FIB).expect("fib runs");
+    }
+    println!("{:>6}: fib(22) in {:?} per run", name, start.elapsed() / runs);
+}
+
+fn main() {
+    bench("tree", Backend::Tree);
+    bench("vm", Backend::Vm);
+}
@@ -4,6 +4,7 @@ set -ex
 
 cargo build --target $TARGET --verbose
 cargo test --target $TARGET
+RASCAL_BACKEND=vm cargo test --target $TARGET
 
 cargo build --target $TARGET --release
 
@@ -1,6 +1,8 @@
 // This module contains the Abstract Sintax Tree representations
 
-use token::{Token};
+use std::fmt;
+
+use token::{Token, Span};
 use primitive::Type;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -8,18 +10,26 @@ pub enum Operation {
     Main(Vec<Node>),
     Identifier(String),
     Constant(Type),
+    List(Vec<Node>),
+    Map(Vec<(String, Node)>),
+    Index(Node, Node),
+    IndexAssign(Node, Node),
     Binary(Node, String, Node),
     Comparison(Node, String, Node),
     CallFunc(Node, Vec<Node>),
-    DefineFunc(Node, Type),
+    DefineFunc(Node, Vec<Param>, Node),
+    Function(Vec<Param>, Node),
     DefineImut(Node, Node),
     DefineVar(Node, Node),
     ReAssign(Node, Node),
-    NegUnary(Node),
+    Unary(String, Node),
     IfElse(Node, Node, Node),
     Loop(Node, Node),
+    ForIn(Node, Node, Node),
     Block(Vec<Node>),
     Return(Node),
+    Break,
+    Continue,
     Print(Node),
     Empty
 }
@@ -28,62 +38,79 @@ pub enum Operation {
 // Node
 //
 // Represents a node inside of the tree
-// each node must have an token and optional nodes
-#[derive(Debug, Clone, PartialEq)]
+// each node must have an token and optional nodes and knows the span of
+// source code it came from
+#[derive(Debug, Clone)]
 pub struct Node{
     pub operation: Box<Operation>,
     pub value: String,
+    pub span: Span,
 }
 
 impl Node {
     pub fn main(statements: Vec<Node>) -> Self {
         Node {
+            span: spanning(&statements),
             operation: Box::new(Operation::Main(statements)),
             value: String::new(),
         }
     }
     pub fn binary(left: Node, token: String, right: Node) -> Self {
         Node {
+            span: left.span.to(right.span),
             operation: Box::new(Operation::Binary(left, token.clone(), right)),
             value: token,
         }
     }
     pub fn comparison(left: Node, token: String, right: Node) -> Self {
         Node {
+            span: left.span.to(right.span),
             operation: Box::new(Operation::Comparison(left, token.clone(), right)),
             value: token,
         }
     }
     pub fn call_function(id: Node, params: Vec<Node>) -> Self {
         Node {
+            span: id.span.to(spanning(&params)),
             operation: Box::new(
                 Operation::CallFunc(id, params)
                 ),
             value: String::from("=")
         }
     }
-    pub fn define_function(id: Node, params: Vec<Node>, block: Node) -> Self {
+    pub fn define_function(id: Node, params: Vec<Param>, block: Node) -> Self {
         Node {
+            span: id.span.to(block.span),
             operation: Box::new(
-                Operation::DefineFunc(id, Type::Func(params, block))
+                Operation::DefineFunc(id, params, block)
                 ),
             value: String::from("=")
         }
     }
+    pub fn function(params: Vec<Param>, body: Node) -> Self {
+        Node {
+            span: body.span,
+            operation: Box::new(Operation::Function(params, body)),
+            value: String::from("fn")
+        }
+    }
     pub fn define_immutable(left: Node, right: Node) -> Self {
         Node {
+            span: left.span.to(right.span),
             operation: Box::new(Operation::DefineImut(left, right)),
             value: String::from("=")
         }
     }
     pub fn define_mutable(left: Node, right: Node) -> Self {
         Node {
+            span: left.span.to(right.span),
             operation: Box::new(Operation::DefineVar(left, right)),
             value: String::from("=")
         }
     }
     pub fn reassign(left: Node, right: Node) -> Self {
         Node {
+            span: left.span.to(right.span),
             operation: Box::new(Operation::ReAssign(left, right)),
             value: String::from("")
         }
@@ -91,48 +118,106 @@ impl Node {
     pub fn indentifier(token: Token) -> Self {
         Node {
             operation: Box::new(Operation::Identifier(token.clone().value)),
-            value: token.value
+            value: token.value,
+            span: token.span
         }
     }
     pub fn constant(token: Token) -> Self {
-        let primitive = Type::from(&token);
+        let primitive = Type::from_token(&token);
         Node {
             operation: Box::new(Operation::Constant(primitive)),
-            value: token.value
+            value: token.value,
+            span: token.span
+        }
+    }
+    pub fn list(items: Vec<Node>) -> Self {
+        Node {
+            span: spanning(&items),
+            operation: Box::new(Operation::List(items)),
+            value: String::new()
+        }
+    }
+    pub fn map(entries: Vec<(String, Node)>) -> Self {
+        let values: Vec<Node> = entries.iter().map(|entry| entry.1.clone()).collect();
+        Node {
+            span: spanning(&values),
+            operation: Box::new(Operation::Map(entries)),
+            value: String::new()
+        }
+    }
+    pub fn index(target: Node, index: Node) -> Self {
+        Node {
+            span: target.span.to(index.span),
+            operation: Box::new(Operation::Index(target, index)),
+            value: String::new()
+        }
+    }
+    pub fn index_assign(target: Node, value: Node) -> Self {
+        Node {
+            span: target.span.to(value.span),
+            operation: Box::new(Operation::IndexAssign(target, value)),
+            value: String::new()
         }
     }
     pub fn unary(token: Token, node: Node) -> Self {
         Node {
-            operation: Box::new(Operation::NegUnary(node)),
+            span: token.span.to(node.span),
+            operation: Box::new(Operation::Unary(token.value.clone(), node)),
             value: token.value
         }
     }
     pub fn _return(node: Node) -> Self {
         Node {
+            span: node.span,
             operation: Box::new(Operation::Return(node)),
             value: String::new()
         }
     }
+    pub fn _break(token: Token) -> Self {
+        Node {
+            span: token.span,
+            operation: Box::new(Operation::Break),
+            value: token.value
+        }
+    }
+    pub fn _continue(token: Token) -> Self {
+        Node {
+            span: token.span,
+            operation: Box::new(Operation::Continue),
+            value: token.value
+        }
+    }
     pub fn print(node: Node) -> Self {
         Node {
+            span: node.span,
             operation: Box::new(Operation::Print(node)),
             value: String::new()
         }
     }
     pub fn ifelse(condition: Node, if_node: Node, else_node: Node) -> Self {
         Node {
+            span: condition.span.to(if_node.span).to(else_node.span),
             operation: Box::new(Operation::IfElse(condition, if_node, else_node)),
             value: String::new()
         }
     }
     pub fn conditional(node:Node, statements: Node) -> Self {
         Node {
+            span: node.span.to(statements.span),
             operation: Box::new(Operation::Loop(node, statements)),
             value: String::new()
         }
     }
+    pub fn for_in(variable: Node, iterable: Node, statements: Node) -> Self {
+        Node {
+            span: variable.span.to(statements.span),
+            operation: Box::new(Operation::ForIn(variable, iterable, statements)),
+            value: String::new()
+        }
+    }
     pub fn block(statements: Vec<Node>) -> Self {
         Node {
+            span: spanning(&statements),
             operation: Box::new(Operation::Block(statements)),
             value: String::new()
         }
@@ -140,7 +225,162 @@ impl Node {
     pub fn empty() -> Self {
         Node {
             operation: Box::new(Operation::Empty),
-            value: String::new()
+            value: String::new(),
+            span: Span::default()
         }
     }
+
+    // at
+    //
+    // It replaces the span of the node, used by the Parser when the node
+    // source is wider than its children (e.g. keywords and delimiters)
+    pub fn at(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    // pretty
+    //
+    // It draws the tree one node per line, the children indented below
+    // their parent, e.g. `1 + x` results in:
+    //   Main
+    //     Binary +
+    //       Constant 1
+    //       Identifier x
+    pub fn pretty(&self) -> String {
+        let mut lines = vec![];
+        self.pretty_lines(0, &mut lines);
+        lines.join("\n")
+    }
+
+    fn pretty_lines(&self, depth: usize, lines: &mut Vec<String>) {
+        let (label, children): (String, Vec<&Node>) = match *self.operation {
+            Operation::Main(ref statements) => (String::from("Main"), statements.iter().collect()),
+            Operation::Identifier(ref name) => (format!("Identifier {}", name), vec![]),
+            Operation::Constant(ref value) => (format!("Constant {}", value.inspect()), vec![]),
+            Operation::List(ref items) => (String::from("List"), items.iter().collect()),
+            Operation::Map(ref entries) => {
+                let keys: Vec<String> = entries.iter().map(|entry| format!("{:?}", entry.0)).collect();
+                (format!("Map {}", keys.join(", ")), entries.iter().map(|entry| &entry.1).collect())
+            },
+            Operation::Index(ref target, ref index) => (String::from("Index"), vec![target, index]),
+            Operation::IndexAssign(ref target, ref value) => (String::from("IndexAssign"), vec![target, value]),
+            Operation::Binary(ref left, ref operator, ref right) =>
+                (format!("Binary {}", operator), vec![left, right]),
+            Operation::Comparison(ref left, ref operator, ref right) =>
+                (format!("Comparison {}", operator), vec![left, right]),
+            Operation::CallFunc(ref name, ref args) =>
+                (format!("CallFunc {}", name.value), args.iter().collect()),
+            Operation::DefineFunc(ref name, ref params, ref body) =>
+                (format!("DefineFunc {} {}", name.value, signature(params)), defaults(params, body)),
+            Operation::Function(ref params, ref body) =>
+                (format!("Function {}", signature(params)), defaults(params, body)),
+            Operation::DefineImut(ref name, ref value) => (format!("DefineImut {}", name.value), vec![value]),
+            Operation::DefineVar(ref name, ref value) => (format!("DefineVar {}", name.value), vec![value]),
+            Operation::ReAssign(ref name, ref value) => (format!("ReAssign {}", name.value), vec![value]),
+            Operation::Unary(ref operator, ref operand) => (format!("Unary {}", operator), vec![operand]),
+            Operation::IfElse(ref condition, ref then, ref otherwise) =>
+                (String::from("IfElse"), vec![condition, then, otherwise]),
+            Operation::Loop(ref condition, ref block) => (String::from("Loop"), vec![condition, block]),
+            Operation::ForIn(ref variable, ref iterable, ref block) =>
+                (format!("ForIn {}", variable.value), vec![iterable, block]),
+            Operation::Block(ref statements) => (String::from("Block"), statements.iter().collect()),
+            Operation::Return(ref value) => (String::from("Return"), vec![value]),
+            Operation::Break => (String::from("Break"), vec![]),
+            Operation::Continue => (String::from("Continue"), vec![]),
+            Operation::Print(ref value) => (String::from("Print"), vec![value]),
+            Operation::Empty => (String::from("Empty"), vec![])
+        };
+
+        lines.push(format!("{}{}", "  ".repeat(depth), label));
+        for child in children {
+            child.pretty_lines(depth + 1, lines);
+        }
+    }
+}
+
+// signature
+//
+// The parameters as they are written in a function definition, without the
+// default values
+fn signature(params: &[Param]) -> String {
+    let names: Vec<String> = params.iter().map(Param::to_string).collect();
+    format!("[{}]", names.join(", "))
+}
+
+// defaults
+//
+// The children of a function node: the default values of its parameters,
+// in order, and its body
+fn defaults<'a>(params: &'a [Param], body: &'a Node) -> Vec<&'a Node> {
+    let mut children: Vec<&Node> = params.iter().filter_map(|param| param.default.as_ref()).collect();
+    children.push(body);
+    children
+}
+
+// The span is not part of a node identity, so trees can be compared
+// regardless of where they were parsed from
+impl PartialEq for Node {
+    fn eq(&self, other: &Node) -> bool {
+        self.operation == other.operation && self.value == other.value
+    }
+}
+
+// Param
+//
+// Represents a function parameter. It may have a default value, used when
+// the argument is missing, or be a rest parameter (`..name`) that collects
+// the remaining arguments into a list
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub default: Option<Node>,
+    pub rest: bool
+}
+
+impl Param {
+    pub fn new(name: String) -> Self {
+        Param { name, default: None, rest: false }
+    }
+    pub fn with_default(name: String, default: Node) -> Self {
+        Param { name, default: Some(default), rest: false }
+    }
+    pub fn rest(name: String) -> Self {
+        Param { name, default: None, rest: true }
+    }
+}
+
+// A parameter is shown as written in the definition, with `..` in place of
+// its default value
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Param { rest: true, ref name, .. } => write!(f, "..{}", name),
+            Param { default: Some(_), ref name, .. } => write!(f, "{} = ..", name),
+            Param { ref name, .. } => write!(f, "{}", name)
+        }
+    }
+}
+
+// arity
+//
+// It gives the minimum and the maximum (None when it has a rest parameter)
+// amount of arguments accepted by the given parameters
+pub fn arity(params: &[Param]) -> (usize, Option<usize>) {
+    let min = params.iter()
+        .rposition(|param| param.default.is_none() && !param.rest)
+        .map_or(0, |position| position + 1);
+    let max = if params.iter().any(|param| param.rest) {
+        None
+    } else {
+        Some(params.len())
+    };
+    (min, max)
+}
+
+fn spanning(nodes: &[Node]) -> Span {
+    match (nodes.first(), nodes.last()) {
+        (Some(first), Some(last)) => first.span.to(last.span),
+        _ => Span::default()
+    }
 }
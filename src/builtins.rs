@@ -0,0 +1,293 @@
+// This module contains the standard library: functions implemented in Rust
+// that every program can call as any other function, e.g. `len([1, 2])`
+
+use std::cmp::Ordering;
+use std::env;
+use std::rc::Rc;
+use primitive::{Type, NativeFunc};
+use frame::Frame;
+use error::RuntimeError;
+use token::Span;
+
+// define
+//
+// It makes the builtins visible in the given frame. Programs can shadow
+// them with their own definitions
+pub fn define(frame: &Frame) {
+    for native in natives() {
+        frame.define_builtin(native.name.clone(), Type::NativeFunc(Rc::new(native)));
+    }
+}
+
+fn natives() -> Vec<NativeFunc> {
+    vec![
+        NativeFunc::new("len", 1, Some(1), len),
+        NativeFunc::new("type_of", 1, Some(1), type_of),
+        NativeFunc::new("str", 1, Some(1), str),
+        NativeFunc::new("int", 1, Some(1), int),
+        NativeFunc::new("abs", 1, Some(1), abs),
+        NativeFunc::new("min", 1, None, |args| extreme("min", args, Ordering::Less)),
+        NativeFunc::new("max", 1, None, |args| extreme("max", args, Ordering::Greater)),
+        NativeFunc::new("range", 1, Some(3), range),
+        NativeFunc::new("push", 2, Some(2), push),
+        NativeFunc::new("keys", 1, Some(1), keys),
+        NativeFunc::new("assert", 1, Some(2), assert),
+        NativeFunc::new("panic", 1, Some(1), panic),
+        NativeFunc::new("args", 0, Some(0), |_| Ok(Type::List(vec![]))),
+    ]
+}
+
+// define_process
+//
+// It makes visible the builtins that reach the process running the program,
+// `env` and `exit`, which are not part of the default ones
+pub fn define_process(frame: &Frame) {
+    let natives = vec![
+        NativeFunc::new("env", 1, Some(1), var),
+        NativeFunc::new("exit", 0, Some(1), exit),
+    ];
+    for native in natives {
+        frame.define_builtin(native.name.clone(), Type::NativeFunc(Rc::new(native)));
+    }
+}
+
+// define_args
+//
+// It makes `args()` result in the given arguments of the program, as strings
+pub fn define_args(frame: &Frame, args: Vec<String>) {
+    let args = Type::List(args.into_iter().map(Type::Str).collect());
+    let native = NativeFunc::new("args", 0, Some(0), move |_| Ok(args.clone()));
+    frame.define_builtin(native.name.clone(), Type::NativeFunc(Rc::new(native)));
+}
+
+// len
+//
+// The amount of characters of a string or of items of a collection
+fn len(args: &[Type]) -> Result<Type, RuntimeError> {
+    let length = match args[0] {
+        Type::Str(ref s) => s.chars().count(),
+        Type::List(ref items) => items.len(),
+        Type::Map(ref entries) => entries.len(),
+        ref other => return Err(unsupported("len", other))
+    };
+    Ok(Type::Int(length as i64))
+}
+
+fn type_of(args: &[Type]) -> Result<Type, RuntimeError> {
+    Ok(Type::Str(args[0].type_name().to_string()))
+}
+
+fn str(args: &[Type]) -> Result<Type, RuntimeError> {
+    Ok(Type::Str(args[0].clone().to_string()))
+}
+
+// int
+//
+// It converts a value to Int: floats are truncated, strings are parsed and
+// booleans are 1 or 0
+fn int(args: &[Type]) -> Result<Type, RuntimeError> {
+    match args[0] {
+        ref value if value.type_name() == "Int" => Ok(value.clone()),
+        Type::Float(value) if value >= i64::MIN as f64 && value < i64::MAX as f64 =>
+            Ok(Type::Int(value as i64)),
+        Type::Float(value) =>
+            Err(invalid("int", format!("{:?} is out of the Int range", value))),
+        Type::Str(ref s) => s.trim().parse().map(Type::Int)
+            .map_err(|_| invalid("int", format!("{:?} is not an integer", s))),
+        Type::Bool(value) => Ok(Type::Int(value as i64)),
+        ref other => Err(unsupported("int", other))
+    }
+}
+
+fn abs(args: &[Type]) -> Result<Type, RuntimeError> {
+    match args[0] {
+        Type::Float(value) => Ok(Type::Float(value.abs())),
+        ref value if value.type_name() == "Int" => {
+            if value.compare(&Type::Int(0), "abs")? == Ordering::Less {
+                Type::Int(0) - value.clone()
+            } else {
+                Ok(value.clone())
+            }
+        },
+        ref other => Err(unsupported("abs", other))
+    }
+}
+
+// extreme
+//
+// The smallest (min) or the greatest (max) of the arguments, or of the
+// items of the only argument when it is a list
+fn extreme(name: &str, args: &[Type], wanted: Ordering) -> Result<Type, RuntimeError> {
+    let values = match args {
+        [Type::List(items)] => items.as_slice(),
+        _ => args
+    };
+
+    let mut values = values.iter();
+    let mut result = match values.next() {
+        Some(value) => value,
+        None => return Err(invalid(name, String::from("the list is empty")))
+    };
+    for value in values {
+        if value.compare(result, name)? == wanted {
+            result = value;
+        }
+    }
+    Ok(result.clone())
+}
+
+// range
+//
+// The list of integers from start (0 when omitted) up to, but not
+// including, end going by step (1 when omitted), e.g. range(3) is [0, 1, 2]
+fn range(args: &[Type]) -> Result<Type, RuntimeError> {
+    let mut bounds = vec![];
+    for arg in args {
+        match *arg {
+            Type::Int(value) => bounds.push(value),
+            ref other => return Err(unsupported("range", other))
+        }
+    }
+
+    let (start, end, step) = match bounds[..] {
+        [end] => (0, end, 1),
+        [start, end] => (start, end, 1),
+        [start, end, step] => (start, end, step),
+        _ => unreachable!("range has up to three arguments")
+    };
+    if step == 0 {
+        return Err(invalid("range", String::from("step can't be zero")))
+    }
+
+    let mut items = vec![];
+    let mut current = start;
+    while (step > 0 && current < end) || (step < 0 && current > end) {
+        items.push(Type::Int(current));
+        current = match current.checked_add(step) {
+            Some(next) => next,
+            None => break
+        };
+    }
+    Ok(Type::List(items))
+}
+
+// push
+//
+// A copy of the list with the value added to its end. Lists are values,
+// so the given list is not changed, e.g. `xs = push(xs, 1)`
+fn push(args: &[Type]) -> Result<Type, RuntimeError> {
+    match args[0] {
+        Type::List(ref items) => {
+            let mut items = items.clone();
+            items.push(args[1].clone());
+            Ok(Type::List(items))
+        },
+        ref other => Err(unsupported("push", other))
+    }
+}
+
+fn keys(args: &[Type]) -> Result<Type, RuntimeError> {
+    match args[0] {
+        Type::Map(ref entries) =>
+            Ok(Type::List(entries.keys().cloned().map(Type::Str).collect())),
+        ref other => Err(unsupported("keys", other))
+    }
+}
+
+// assert
+//
+// It stops the program with the given message, or a default one, when
+// the condition is false
+fn assert(args: &[Type]) -> Result<Type, RuntimeError> {
+    if args[0].as_bool() {
+        return Ok(Type::Nil)
+    }
+    let message = match args.get(1) {
+        Some(message) => message.clone().to_string(),
+        None => String::from("assertion failed")
+    };
+    Err(RuntimeError::Panic { message, span: Span::default() })
+}
+
+fn panic(args: &[Type]) -> Result<Type, RuntimeError> {
+    Err(RuntimeError::Panic { message: args[0].clone().to_string(), span: Span::default() })
+}
+
+// var
+//
+// The value of an environment variable, or Nil when it is not set
+fn var(args: &[Type]) -> Result<Type, RuntimeError> {
+    match args[0] {
+        Type::Str(ref name) => Ok(env::var(name).map(Type::Str).unwrap_or(Type::Nil)),
+        ref other => Err(unsupported("env", other))
+    }
+}
+
+// exit
+//
+// It stops the program with the given exit status, 0 by default. It is an
+// error for the interpreter, which the binary turns into its exit status
+fn exit(args: &[Type]) -> Result<Type, RuntimeError> {
+    let code = match args.first() {
+        None => 0,
+        Some(&Type::Int(code)) if code >= i32::MIN as i64 && code <= i32::MAX as i64 => code as i32,
+        Some(&Type::Int(code)) => return Err(invalid("exit", format!("{} is not an exit status", code))),
+        Some(other) => return Err(unsupported("exit", other))
+    };
+    Err(RuntimeError::Exit { code, span: Span::default() })
+}
+
+fn unsupported(name: &str, operand: &Type) -> RuntimeError {
+    RuntimeError::InvalidOperand {
+        op: name.to_string(),
+        operand: operand.type_name().to_string(),
+        span: Span::default()
+    }
+}
+
+fn invalid(name: &str, reason: String) -> RuntimeError {
+    RuntimeError::InvalidArgument { name: name.to_string(), reason, span: Span::default() }
+}
+
+#[cfg(test)]
+mod test {
+    use builtins::{range, extreme, int, exit};
+    use error::RuntimeError;
+    use primitive::Type;
+    use std::cmp::Ordering;
+
+    fn ints(items: Vec<i64>) -> Type {
+        Type::List(items.into_iter().map(Type::Int).collect())
+    }
+
+    #[test]
+    fn it_builds_ranges() {
+        assert_eq!(Ok(ints(vec![0, 1, 2])), range(&[Type::Int(3)]));
+        assert_eq!(Ok(ints(vec![5, 3, 1])), range(&[Type::Int(5), Type::Int(0), Type::Int(-2)]));
+        assert_eq!(Ok(ints(vec![])), range(&[Type::Int(2), Type::Int(1)]));
+        assert!(range(&[Type::Int(0), Type::Int(1), Type::Int(0)]).is_err());
+    }
+
+    #[test]
+    fn it_finds_extremes_of_arguments_or_lists() {
+        assert_eq!(Ok(Type::Int(1)),
+                   extreme("min", &[Type::Int(3), Type::Float(1.0), Type::Int(1)], Ordering::Less));
+        assert_eq!(Ok(Type::Int(3)), extreme("max", &[ints(vec![1, 3, 2])], Ordering::Greater));
+        assert!(extreme("max", &[ints(vec![])], Ordering::Greater).is_err());
+    }
+
+    #[test]
+    fn it_converts_to_int() {
+        assert_eq!(Ok(Type::Int(-2)), int(&[Type::Float(-2.7)]));
+        assert_eq!(Ok(Type::Int(42)), int(&[Type::Str(String::from(" 42 "))]));
+        assert_eq!(Ok(Type::Int(1)), int(&[Type::Bool(true)]));
+        assert!(int(&[Type::Str(String::from("4x"))]).is_err());
+    }
+
+    #[test]
+    fn it_exits_with_a_status() {
+        assert_eq!(Err(RuntimeError::Exit { code: 0, span: Default::default() }), exit(&[]));
+        assert_eq!(Err(RuntimeError::Exit { code: 3, span: Default::default() }), exit(&[Type::Int(3)]));
+        assert!(exit(&[Type::Int(1 << 40)]).is_err());
+    }
+}
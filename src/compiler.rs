@@ -0,0 +1,796 @@
+// This module lowers the Abstract Sintax Tree into bytecode for the Vm: a
+// flat list of instructions for each function, with a pool of constants,
+// numbered slots for the local variables and jumps for the control flow.
+//
+// Variables are resolved while compiling. The ones defined inside of a
+// function (or a block of the main program) live in slots, the ones of
+// enclosing functions are captured when the function value is created and
+// the ones defined at the top of the main program are globals, looked up by
+// name in the Interpreter global Frame. Errors the compiler already knows
+// about, e.g. reassigning a `let`, become instructions that fail when (and
+// only if) they are reached, so programs behave as in the tree walker
+
+use std::rc::Rc;
+use ast::{Node, Operation, Param, arity};
+use primitive::Type;
+use error::RuntimeError;
+use token::Span;
+use stacker;
+use interpreter::{RED_ZONE, STACK_SEGMENT};
+
+// Variable
+//
+// Where an instruction reads or writes a variable
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variable {
+    Local(usize),
+    Capture(usize),
+    // `name` is an index of the chunk names and `visible` of the names in
+    // scope, used to suggest a similar one when the global doesn't exist
+    Global { name: usize, visible: usize },
+}
+
+// Capture
+//
+// Where a function value takes a captured variable from when it is
+// created: a slot of the function creating it or one of its captures
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Capture {
+    Local(usize),
+    Parent(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    Constant(usize),
+    Nil,
+    Pop,
+    Dup,
+    Get(Variable),
+    // It replaces the value of a variable with the top of the stack
+    Set(Variable),
+    // It pops a value into a new variable, a global can't be defined twice
+    Define(Variable, bool),
+    // It validates that a global exists and is mutable before assigning it
+    CheckAssign(Variable),
+    // It validates that the main program didn't define the given name yet
+    CheckUndefined(usize),
+    // It pops a value and the given amount of indexes and assigns the value
+    // to the item found following them
+    SetIndex(Variable, usize),
+    CheckCallable(usize),
+    CheckArity(usize, usize),
+    // It calls the function below the given amount of arguments on the
+    // stack, by the given name
+    Call(usize, usize),
+    // It calls like Call but in place of the running function, whose result
+    // is the result of the call
+    TailCall(usize, usize),
+    Closure(usize),
+    Return,
+    Jump(usize),
+    JumpIfNotTrue(usize),
+    // It jumps when the given parameter received an argument
+    JumpIfGiven(usize, usize),
+    Binary(String),
+    Comparison(String),
+    Unary(String),
+    List(usize),
+    Map(Vec<String>),
+    Index,
+    // It pops an iterable and keeps its items in the given slot and the
+    // position of the next one in the slot after it
+    Iterate(usize),
+    // It pushes the next item kept in the given slot, or jumps when there
+    // are no more items
+    Next(usize, usize),
+    Print,
+    Fail(usize),
+}
+
+// Chunk
+//
+// The bytecode of a function. `spans` has the source location of each
+// instruction, used to place the errors it raises
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<Instruction>,
+    pub spans: Vec<Span>,
+    pub constants: Vec<Type>,
+    pub names: Vec<String>,
+    pub visible: Vec<Vec<String>>,
+    pub functions: Vec<Rc<Prototype>>,
+    pub errors: Vec<RuntimeError>,
+}
+
+// Prototype
+//
+// A compiled function: its code and what the Vm needs to call it. The
+// arguments go to the first slots and a rest parameter takes a list of the
+// remaining ones
+#[derive(Debug, Default)]
+pub struct Prototype {
+    pub chunk: Chunk,
+    pub min: usize,
+    pub max: Option<usize>,
+    pub rest: Option<usize>,
+    pub slots: usize,
+    pub captures: Vec<Capture>,
+    // The parameters as they are written, shown by the REPL completion
+    pub params: Vec<String>,
+}
+
+struct Local {
+    name: String,
+    slot: usize,
+    mutable: bool,
+    // A function hoisted to the start of its scope that is not defined yet:
+    // only the functions created before its definition can see it
+    hoisted: bool,
+}
+
+struct Loop {
+    start: usize,
+    breaks: Vec<usize>,
+}
+
+// Function
+//
+// The state of a function being compiled. The main program is a function
+// whose outermost scope has globals instead of slots
+struct Function {
+    prototype: Prototype,
+    scopes: Vec<Vec<Local>>,
+    captures: Vec<(String, bool)>,
+    loops: Vec<Loop>,
+    slots: usize,
+}
+
+impl Function {
+    fn new() -> Self {
+        Function {
+            prototype: Prototype::default(),
+            scopes: vec![],
+            captures: vec![],
+            loops: vec![],
+            slots: 0,
+        }
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.scopes.iter().any(|scope| {
+            scope.iter().any(|local| local.name == name && !local.hoisted)
+        })
+    }
+
+    fn local(&self, name: &str, hoisted: bool) -> Option<(usize, bool)> {
+        self.scopes.iter().rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|local| local.name == name && (hoisted || !local.hoisted))
+            .map(|local| (local.slot, local.mutable))
+    }
+
+    fn slot(&mut self) -> usize {
+        let slot = self.slots;
+        self.slots += 1;
+        self.prototype.slots = self.prototype.slots.max(self.slots);
+        slot
+    }
+
+    fn declare(&mut self, name: String, mutable: bool) -> usize {
+        self.push(name, mutable, false)
+    }
+
+    fn push(&mut self, name: String, mutable: bool, hoisted: bool) -> usize {
+        let slot = self.slot();
+        self.scopes.last_mut()
+            .expect("locals are declared inside of a scope")
+            .push(Local { name, slot, mutable, hoisted });
+        slot
+    }
+
+    // settle
+    //
+    // It defines a function hoisted in the innermost scope, resulting in
+    // its slot. None when the function was not hoisted
+    fn settle(&mut self, name: &str) -> Option<usize> {
+        let scope = self.scopes.last_mut()?;
+        let local = scope.iter_mut().find(|local| local.name == name && local.hoisted)?;
+        local.hoisted = false;
+        Some(local.slot)
+    }
+}
+
+// # Compiler
+//
+// It compiles a tree into the Prototype of the main program
+pub struct Compiler {
+    functions: Vec<Function>,
+}
+
+impl Compiler {
+    pub fn compile(tree: Node) -> Prototype {
+        let mut compiler = Compiler { functions: vec![Function::new()] };
+        let span = tree.span;
+        compiler.node(tree);
+        compiler.emit(Instruction::Return, span);
+        compiler.functions.pop().expect("the main program is compiled").prototype
+    }
+
+    fn current(&mut self) -> &mut Function {
+        self.functions.last_mut().expect("there is always a function being compiled")
+    }
+
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.current().prototype.chunk
+    }
+
+    fn emit(&mut self, instruction: Instruction, span: Span) -> usize {
+        let chunk = self.chunk();
+        chunk.code.push(instruction);
+        chunk.spans.push(span);
+        chunk.code.len() - 1
+    }
+
+    // patch
+    //
+    // It points the jump at the given position to the next instruction
+    fn patch(&mut self, position: usize) {
+        let target = self.chunk().code.len();
+        match self.chunk().code[position] {
+            Instruction::Jump(ref mut to) |
+            Instruction::JumpIfNotTrue(ref mut to) |
+            Instruction::JumpIfGiven(_, ref mut to) |
+            Instruction::Next(_, ref mut to) => *to = target,
+            _ => unreachable!("only jumps are patched")
+        }
+    }
+
+    // tail_calls
+    //
+    // It turns the calls followed by returning, right away or after jumps,
+    // into tail calls, so calls in tail position don't grow the call stack
+    fn tail_calls(&mut self) {
+        let code = &mut self.chunk().code;
+        for position in 0..code.len() {
+            if let Instruction::Call(name, given) = code[position] {
+                let mut next = position + 1;
+                // It follows no more jumps than instructions, so it ends
+                for _ in 0..code.len() {
+                    match code[next] {
+                        Instruction::Jump(target) => next = target,
+                        _ => break
+                    }
+                }
+                if code[next] == Instruction::Return {
+                    code[position] = Instruction::TailCall(name, given);
+                }
+            }
+        }
+    }
+
+    fn constant(&mut self, value: Type) -> usize {
+        let chunk = self.chunk();
+        chunk.constants.push(value);
+        chunk.constants.len() - 1
+    }
+
+    fn name(&mut self, name: &str) -> usize {
+        let chunk = self.chunk();
+        match chunk.names.iter().position(|known| known == name) {
+            Some(index) => index,
+            None => {
+                chunk.names.push(name.to_string());
+                chunk.names.len() - 1
+            }
+        }
+    }
+
+    fn fail(&mut self, error: RuntimeError) {
+        let span = error.span();
+        let chunk = self.chunk();
+        chunk.errors.push(error);
+        let index = chunk.errors.len() - 1;
+        self.emit(Instruction::Fail(index), span);
+    }
+
+    // is_global_scope
+    //
+    // Whether a definition here is a global: the top of the main program
+    fn is_global_scope(&self) -> bool {
+        self.functions.len() == 1 && self.functions[0].scopes.is_empty()
+    }
+
+    fn is_main(&self) -> bool {
+        self.functions.len() == 1
+    }
+
+    // global
+    //
+    // A global variable, keeping the names in scope to suggest from
+    fn global(&mut self, name: &str) -> Variable {
+        let visible: Vec<String> = self.functions.iter()
+            .flat_map(|function| function.scopes.iter())
+            .flat_map(|scope| scope.iter().map(|local| local.name.clone()))
+            .collect();
+        let name = self.name(name);
+        let chunk = self.chunk();
+        let visible = match chunk.visible.iter().position(|known| *known == visible) {
+            Some(index) => index,
+            None => {
+                chunk.visible.push(visible);
+                chunk.visible.len() - 1
+            }
+        };
+        Variable::Global { name, visible }
+    }
+
+    // resolve
+    //
+    // It finds where a variable lives and whether it is mutable, capturing
+    // it when it belongs to an enclosing function. None means a global
+    fn resolve(&mut self, level: usize, name: &str) -> Option<(Variable, bool)> {
+        let capturing = level + 1 < self.functions.len();
+        if let Some((slot, mutable)) = self.functions[level].local(name, capturing) {
+            return Some((Variable::Local(slot), mutable))
+        }
+        let captures = &self.functions[level].captures;
+        if let Some(index) = captures.iter().position(|capture| capture.0 == name) {
+            return Some((Variable::Capture(index), captures[index].1))
+        }
+        if level == 0 {
+            return None
+        }
+
+        let capture = match self.resolve(level - 1, name)? {
+            (Variable::Local(slot), mutable) => (Capture::Local(slot), mutable),
+            (Variable::Capture(index), mutable) => (Capture::Parent(index), mutable),
+            _ => return None
+        };
+        let function = &mut self.functions[level];
+        function.captures.push((name.to_string(), capture.1));
+        function.prototype.captures.push(capture.0);
+        Some((Variable::Capture(function.captures.len() - 1), capture.1))
+    }
+
+    fn variable(&mut self, name: &str) -> (Variable, bool) {
+        let level = self.functions.len() - 1;
+        match self.resolve(level, name) {
+            Some(variable) => variable,
+            None => (self.global(name), true)
+        }
+    }
+
+    // check_undefined
+    //
+    // It validates that a name can be defined in the current scope. It
+    // results in false when the definition is known to fail
+    fn check_undefined(&mut self, name: &str, span: Span) -> bool {
+        if self.current().has(name) {
+            self.fail(RuntimeError::AlreadyDefined { name: name.to_string(), span });
+            return false
+        }
+        if self.is_main() {
+            let name = self.name(name);
+            self.emit(Instruction::CheckUndefined(name), span);
+        }
+        true
+    }
+
+    // define
+    //
+    // It defines a variable with the value on top of the stack
+    fn define(&mut self, name: String, mutable: bool, span: Span) {
+        if self.is_global_scope() {
+            let variable = self.global(&name);
+            self.emit(Instruction::Define(variable, mutable), span);
+        } else {
+            let slot = self.current().declare(name, mutable);
+            self.emit(Instruction::Define(Variable::Local(slot), mutable), span);
+        }
+    }
+
+    // check_assign
+    //
+    // It validates that a variable can be reassigned, resulting in where it
+    // lives. None when the assignment is known to fail
+    fn check_assign(&mut self, name: String, span: Span) -> Option<Variable> {
+        match self.variable(&name) {
+            (variable @ Variable::Global { .. }, _) => {
+                self.emit(Instruction::CheckAssign(variable), span);
+                Some(variable)
+            },
+            (variable, true) => Some(variable),
+            (_, false) => {
+                self.fail(RuntimeError::ImmutableReassign { name, span });
+                None
+            }
+        }
+    }
+
+    fn scoped<F: FnOnce(&mut Compiler)>(&mut self, compile: F) {
+        self.current().scopes.push(vec![]);
+        let slots = self.current().slots;
+        compile(self);
+        let function = self.current();
+        function.scopes.pop();
+        function.slots = slots;
+    }
+
+    // hoist
+    //
+    // It reserves, when a scope starts, the variables of the functions it
+    // defines by name, so the functions defined before them can call them,
+    // e.g. two mutually recursive functions. Globals don't need it, they are
+    // looked up by name
+    fn hoist(&mut self, statements: &[Node], span: Span) {
+        for statement in statements {
+            if let Operation::DefineFunc(ref lnode, _, _) = *statement.operation {
+                let name = &lnode.value;
+                if self.current().has(name) || self.current().local(name, true).is_some() {
+                    continue
+                }
+                let slot = self.current().push(name.clone(), false, true);
+                self.emit(Instruction::Nil, span);
+                self.emit(Instruction::Define(Variable::Local(slot), false), span);
+            }
+        }
+    }
+
+    // statements
+    //
+    // It compiles a list of statements that results in the value of the
+    // last one
+    fn statements(&mut self, statements: Vec<Node>, span: Span) {
+        if !self.is_global_scope() {
+            self.hoist(&statements, span);
+        }
+        if statements.is_empty() {
+            self.emit(Instruction::Nil, span);
+        }
+        let last = statements.len().saturating_sub(1);
+        for (position, statement) in statements.into_iter().enumerate() {
+            let span = statement.span;
+            self.node(statement);
+            if position != last {
+                self.emit(Instruction::Pop, span);
+            }
+        }
+    }
+
+    fn node(&mut self, tree: Node) {
+        stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, || self.operation(tree))
+    }
+
+    // operation
+    //
+    // It compiles a node into instructions that leave its value on the stack
+    fn operation(&mut self, tree: Node) {
+        let Node { operation, span, value } = tree;
+        match *operation {
+            Operation::Main(statements) => self.statements(statements, span),
+
+            Operation::Block(statements) =>
+                self.scoped(|compiler| compiler.statements(statements, span)),
+
+            Operation::Constant(constant) => {
+                let index = self.constant(constant);
+                self.emit(Instruction::Constant(index), span);
+            },
+
+            Operation::Identifier(name) => {
+                let (variable, _) = self.variable(&name);
+                self.emit(Instruction::Get(variable), span);
+            },
+
+            Operation::Binary(lnode, operator, rnode) => {
+                self.node(lnode);
+                self.node(rnode);
+                self.emit(Instruction::Binary(operator), span);
+            },
+
+            Operation::Comparison(lnode, operator, rnode) => {
+                self.node(lnode);
+                self.node(rnode);
+                self.emit(Instruction::Comparison(operator), span);
+            },
+
+            Operation::Unary(operator, node) => {
+                self.node(node);
+                self.emit(Instruction::Unary(operator), span);
+            },
+
+            Operation::List(items) => {
+                let length = items.len();
+                for item in items {
+                    self.node(item);
+                }
+                self.emit(Instruction::List(length), span);
+            },
+
+            Operation::Map(entries) => {
+                let mut keys = vec![];
+                for (key, value) in entries {
+                    keys.push(key);
+                    self.node(value);
+                }
+                self.emit(Instruction::Map(keys), span);
+            },
+
+            Operation::Index(target, index) => {
+                self.node(target);
+                self.node(index);
+                self.emit(Instruction::Index, span);
+            },
+
+            Operation::IndexAssign(target, rnode) => {
+                let mut indexes = vec![];
+                let mut target = target;
+                while let Operation::Index(inner, index) = *target.operation {
+                    indexes.push(index);
+                    target = inner;
+                }
+                let depth = indexes.len();
+                for index in indexes.into_iter().rev() {
+                    self.node(index);
+                }
+
+                if let Some(variable) = self.check_assign(target.value, target.span) {
+                    self.node(rnode);
+                    self.emit(Instruction::SetIndex(variable, depth), span);
+                }
+            },
+
+            Operation::DefineImut(lnode, rnode) => {
+                self.node(rnode);
+                if self.check_undefined(&lnode.value, lnode.span) {
+                    self.define(lnode.value, false, lnode.span);
+                }
+                self.emit(Instruction::Nil, span);
+            },
+
+            Operation::DefineVar(lnode, rnode) => {
+                if self.check_undefined(&lnode.value, lnode.span) {
+                    self.node(rnode);
+                    self.emit(Instruction::Dup, span);
+                    self.define(lnode.value, true, lnode.span);
+                }
+            },
+
+            Operation::DefineFunc(lnode, params, body) => {
+                if self.check_undefined(&lnode.value, lnode.span) {
+                    // The name is defined before compiling the function, so
+                    // it can call itself
+                    let variable = match self.current().settle(&lnode.value) {
+                        Some(slot) => Variable::Local(slot),
+                        None => {
+                            self.emit(Instruction::Nil, span);
+                            self.define(lnode.value.clone(), false, lnode.span);
+                            self.variable(&lnode.value).0
+                        }
+                    };
+                    self.function(params, body, span);
+                    self.emit(Instruction::Set(variable), span);
+                    self.emit(Instruction::Pop, span);
+                    self.emit(Instruction::Nil, span);
+                }
+            },
+
+            Operation::Function(params, body) => self.function(params, body, span),
+
+            Operation::ReAssign(lnode, rnode) => {
+                if let Some(variable) = self.check_assign(lnode.value, lnode.span) {
+                    self.node(rnode);
+                    self.emit(Instruction::Set(variable), span);
+                }
+            },
+
+            Operation::CallFunc(nodename, args) => {
+                let name = nodename.value;
+                let (variable, _) = self.variable(&name);
+                self.emit(Instruction::Get(variable), nodename.span);
+                let name = self.name(&name);
+                self.emit(Instruction::CheckCallable(name), nodename.span);
+                self.emit(Instruction::CheckArity(name, args.len()), span);
+
+                let given = args.len();
+                for arg in args {
+                    self.node(arg);
+                }
+                self.emit(Instruction::Call(name, given), span);
+            },
+
+            Operation::IfElse(conditional, lnode, rnode) => {
+                self.node(conditional);
+                let otherwise = self.emit(Instruction::JumpIfNotTrue(0), span);
+                self.node(lnode);
+                let end = self.emit(Instruction::Jump(0), span);
+                self.patch(otherwise);
+                self.node(rnode);
+                self.patch(end);
+            },
+
+            Operation::Loop(conditional, block) => {
+                let start = self.chunk().code.len();
+                self.node(conditional);
+                let exit = self.emit(Instruction::JumpIfNotTrue(0), span);
+
+                self.current().loops.push(Loop { start, breaks: vec![exit] });
+                self.node(block);
+                self.emit(Instruction::Pop, span);
+                self.emit(Instruction::Jump(start), span);
+                self.end_loop(span);
+            },
+
+            Operation::ForIn(variable, iterable, block) => {
+                if !self.check_undefined(&variable.value, variable.span) {
+                    self.emit(Instruction::Nil, span);
+                    return
+                }
+                self.node(iterable);
+
+                self.scoped(|compiler| {
+                    let items = compiler.current().slot();
+                    compiler.current().slot();
+                    compiler.emit(Instruction::Iterate(items), span);
+
+                    let start = compiler.emit(Instruction::Next(items, 0), span);
+                    compiler.current().loops.push(Loop { start, breaks: vec![start] });
+                    compiler.scoped(|compiler| {
+                        let slot = compiler.current().declare(variable.value, false);
+                        compiler.emit(Instruction::Define(Variable::Local(slot), false), span);
+                        compiler.node(block);
+                    });
+                    compiler.emit(Instruction::Pop, span);
+                    compiler.emit(Instruction::Jump(start), span);
+                    compiler.end_loop(span);
+                });
+            },
+
+            Operation::Return(node) => {
+                self.node(node);
+                self.emit(Instruction::Return, span);
+            },
+
+            Operation::Break => match self.current().loops.last().is_some() {
+                true => {
+                    let jump = self.emit(Instruction::Jump(0), span);
+                    self.current().loops.last_mut().unwrap().breaks.push(jump);
+                },
+                false => self.fail(RuntimeError::OutsideLoop { keyword: value, span })
+            },
+
+            Operation::Continue => match self.current().loops.last().map(|l| l.start) {
+                Some(start) => {
+                    self.emit(Instruction::Jump(start), span);
+                },
+                None => self.fail(RuntimeError::OutsideLoop { keyword: value, span })
+            },
+
+            Operation::Print(node) => {
+                self.node(node);
+                self.emit(Instruction::Print, span);
+            },
+
+            Operation::Empty => {
+                self.emit(Instruction::Nil, span);
+            }
+        }
+    }
+
+    // end_loop
+    //
+    // It points the breaks of the innermost loop after it, where the loop
+    // results in Nil
+    fn end_loop(&mut self, span: Span) {
+        let finished = self.current().loops.pop().expect("a loop is being compiled");
+        for jump in finished.breaks {
+            self.patch(jump);
+        }
+        self.emit(Instruction::Nil, span);
+    }
+
+    // function
+    //
+    // It compiles a function and the instruction that creates its value.
+    // Missing arguments take their default value in the function prologue
+    fn function(&mut self, params: Vec<Param>, body: Node, span: Span) {
+        let (min, max) = arity(&params);
+        let mut function = Function::new();
+        function.prototype.min = min;
+        function.prototype.max = max;
+        function.prototype.params = params.iter().map(Param::to_string).collect();
+        function.scopes.push(vec![]);
+        self.functions.push(function);
+
+        // Every parameter has its slot from the start, but it is only in
+        // scope for the defaults of the following ones
+        let slots: Vec<usize> = params.iter().map(|_| self.current().slot()).collect();
+        for (param, slot) in params.into_iter().zip(slots) {
+            if param.rest {
+                self.current().prototype.rest = Some(slot);
+            } else if slot >= min {
+                let given = self.emit(Instruction::JumpIfGiven(slot, 0), span);
+                match param.default {
+                    Some(default) => self.node(default),
+                    None => {
+                        self.emit(Instruction::Nil, span);
+                    }
+                }
+                self.emit(Instruction::Define(Variable::Local(slot), true), span);
+                self.patch(given);
+            }
+            self.current().scopes[0].push(Local {
+                name: param.name, slot, mutable: true, hoisted: false
+            });
+        }
+
+        self.node(body);
+        self.emit(Instruction::Return, span);
+        self.tail_calls();
+
+        let compiled = self.functions.pop().expect("the function is compiled").prototype;
+        let chunk = self.chunk();
+        chunk.functions.push(Rc::new(compiled));
+        let index = chunk.functions.len() - 1;
+        self.emit(Instruction::Closure(index), span);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use compiler::{Compiler, Instruction, Variable};
+    use parser::Parser;
+    use token::Tokenizer;
+
+    fn compile(source: &str) -> Vec<Instruction> {
+        let tree = Parser::new(Tokenizer::new(String::from(source))).parse().unwrap();
+        Compiler::compile(tree).chunk.code
+    }
+
+    #[test]
+    fn it_compiles_expressions_to_stack_instructions() {
+        assert_eq!(vec![
+            Instruction::Constant(0),
+            Instruction::Constant(1),
+            Instruction::Constant(2),
+            Instruction::Binary(String::from("*")),
+            Instruction::Binary(String::from("+")),
+            Instruction::Return,
+        ], compile("1 + 2 * 3"));
+    }
+
+    #[test]
+    fn it_keeps_block_variables_in_slots() {
+        let code = compile("{ let x = 1; x }");
+
+        assert!(code.contains(&Instruction::Define(Variable::Local(0), false)));
+        assert!(code.contains(&Instruction::Get(Variable::Local(0))));
+    }
+
+    #[test]
+    fn it_jumps_over_the_branch_not_taken() {
+        let code = compile("if true { 1 else 2 }");
+
+        assert_eq!(Instruction::JumpIfNotTrue(4), code[1]);
+        assert_eq!(Instruction::Jump(5), code[3]);
+    }
+
+    #[test]
+    fn it_fails_reassigning_immutable_locals_when_reached() {
+        let code = compile("{ let x = 1; if false { x = 2 } }");
+
+        assert!(code.contains(&Instruction::Fail(0)));
+    }
+
+    #[test]
+    fn it_compiles_calls_in_tail_position_to_tail_calls() {
+        let tree = Parser::new(Tokenizer::new(String::from(
+            "let f = fn [n] { if n == 0 { g(n) else f(n - 1) } }"
+        ))).parse().unwrap();
+        let code = &Compiler::compile(tree).chunk.functions[0].chunk.code;
+
+        assert_eq!(2, code.iter().filter(|i| matches!(i, Instruction::TailCall(..))).count());
+        assert!(!code.iter().any(|i| matches!(i, Instruction::Call(..))));
+    }
+}
@@ -0,0 +1,200 @@
+// This module renders errors the way modern compilers do: the location in
+// the source file, the offending line, a caret under the span and an optional
+// help note. Example:
+//```
+//   Variable sun doesn't exist in this context
+//    --> example.rl:3:1
+//     |
+//   3 | sun + 1
+//     | ^^^
+//     = help: did you mean `sum`?
+//```
+
+use std::fmt;
+use std::io::{self, IsTerminal};
+use token::Span;
+use error::{Error, SyntaxError, RuntimeError};
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+const RESET: &str = "\x1b[0m";
+
+// Diagnostic
+//
+// Represents an error ready to be shown to the user, no matter if it came
+// from the Parser or from the Interpreter
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(message: String, span: Span, help: Option<String>) -> Self {
+        Diagnostic { message, span, help }
+    }
+
+    // render
+    //
+    // It renders the diagnostic pointing to the span inside of the given
+    // source. Colors are applied with ANSI escape codes when `colored`
+    pub fn render(&self, file: &str, source: &str, colored: bool) -> String {
+        let paint = |color: &str, text: &str| {
+            if colored { format!("{}{}{}", color, text, RESET) } else { text.to_string() }
+        };
+
+        let mut output = format!("{}\n", paint(RED, &self.message));
+        if self.span.is_known() {
+            let line = source.lines().nth(self.span.line - 1).unwrap_or("");
+            let number = self.span.line.to_string();
+            let gutter = " ".repeat(number.len());
+
+            let remaining = line.chars().count().saturating_sub(self.span.column - 1);
+            let width = source.get(self.span.start..self.span.end)
+                .map(|text| text.chars().take_while(|c| *c != '\n').count())
+                .unwrap_or(1);
+            let underline = "^".repeat(width.min(remaining).max(1));
+
+            output.push_str(&format!("{}{} {}:{}:{}\n",
+                                     gutter, paint(BLUE, "-->"), file,
+                                     self.span.line, self.span.column));
+            output.push_str(&format!("{} {}\n", gutter, paint(BLUE, "|")));
+            output.push_str(&format!("{} {}\n",
+                                     paint(BLUE, &format!("{} |", number)), line));
+            output.push_str(&format!("{} {} {}{}\n",
+                                     gutter, paint(BLUE, "|"),
+                                     " ".repeat(self.span.column - 1),
+                                     paint(RED, &underline)));
+        }
+
+        if let Some(ref help) = self.help {
+            let indent = " ".repeat(self.span.line.to_string().len());
+            output.push_str(&format!("{} {} {}\n",
+                                     indent, paint(BLUE, "="),
+                                     paint(CYAN, &format!("help: {}", help))));
+        }
+        output
+    }
+
+    // render_to_terminal
+    //
+    // It renders the diagnostic to be printed on stderr, with colors only
+    // when stderr is a TTY
+    pub fn render_to_terminal(&self, file: &str, source: &str) -> String {
+        self.render(file, source, io::stderr().is_terminal())
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.span.is_known() {
+            write!(f, "{} at line {}, column {}",
+                   self.message, self.span.line, self.span.column)
+        } else {
+            write!(f, "{}", self.message)
+        }
+    }
+}
+
+impl From<SyntaxError> for Diagnostic {
+    fn from(error: SyntaxError) -> Self {
+        Diagnostic::new(format!("Sintax error: {}", error.message()), error.span, None)
+    }
+}
+
+impl From<RuntimeError> for Diagnostic {
+    fn from(error: RuntimeError) -> Self {
+        Diagnostic::new(error.message(), error.span(), error.help())
+    }
+}
+
+impl From<Error> for Diagnostic {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Syntax(error) => Diagnostic::from(error),
+            Error::Runtime(error) => Diagnostic::from(error)
+        }
+    }
+}
+
+// suggest
+//
+// It looks for the candidate closest to the given name, so a typo like `sun`
+// can be answered with "did you mean `sum`?"
+pub fn suggest<'a, I>(name: &str, candidates: I) -> Option<String>
+    where I: IntoIterator<Item = &'a String> {
+    let threshold = (name.chars().count() / 3).max(1);
+    candidates.into_iter()
+        .filter(|candidate| candidate.as_str() != name)
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|&(distance, _)| distance <= threshold)
+        .min()
+        .map(|(_, candidate)| candidate.clone())
+}
+
+// distance
+//
+// Levenshtein distance between two words
+fn distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..right.len() + 1).collect();
+
+    for (i, lchar) in left.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, rchar) in right.iter().enumerate() {
+            let cost = if lchar == *rchar { 0 } else { 1 };
+            let value = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+            current.push(value);
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use diagnostics::{Diagnostic, suggest};
+    use token::Span;
+
+    #[test]
+    fn it_renders_source_snippet_with_caret() {
+        let source = "var sum = 0;\nsun + 1";
+        let span = Span { start: 13, end: 16, line: 2, column: 1 };
+        let diagnostic = Diagnostic::new(
+            String::from("Variable sun doesn't exist in this context"),
+            span,
+            Some(String::from("did you mean `sum`?")));
+
+        assert_eq!("Variable sun doesn't exist in this context\n \
+                     --> main.rl:2:1\n  \
+                     |\n\
+                    2 | sun + 1\n  \
+                     | ^^^\n  \
+                     = help: did you mean `sum`?\n",
+                   diagnostic.render("main.rl", source, false));
+    }
+
+    #[test]
+    fn it_renders_with_colors() {
+        let source = "x";
+        let span = Span { start: 0, end: 1, line: 1, column: 1 };
+        let diagnostic = Diagnostic::new(String::from("oops"), span, None);
+
+        let output = diagnostic.render("main.rl", source, true);
+        assert!(output.starts_with("\x1b[1;31moops\x1b[0m\n"));
+        assert!(output.contains("\x1b[1;31m^\x1b[0m"));
+    }
+
+    #[test]
+    fn it_suggests_closest_name() {
+        let names = vec![String::from("sum"), String::from("number")];
+
+        assert_eq!(Some(String::from("sum")), suggest("sun", &names));
+        assert_eq!(Some(String::from("number")), suggest("nunber", &names));
+        assert_eq!(None, suggest("foo", &names));
+    }
+}
@@ -0,0 +1,280 @@
+// This module contains the errors reported while reading and running a program
+
+use std::fmt;
+use token::{Kind, Token, Span};
+
+// SyntaxError
+//
+// Represents a program that doesn't follow the grammar. It carries the kinds
+// of token the Parser expected, the token found instead (None when the source
+// ended) and the location where it was found
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub expected: Vec<Kind>,
+    pub found: Option<Token>,
+    pub span: Span,
+}
+
+impl SyntaxError {
+    pub fn new(expected: Vec<Kind>, found: Option<Token>, span: Span) -> Self {
+        SyntaxError { expected, found, span }
+    }
+
+    // message
+    //
+    // It describes the error without its location
+    pub fn message(&self) -> String {
+        let expected = self.expected.iter()
+            .map(|kind| format!("{:?}", kind))
+            .collect::<Vec<String>>()
+            .join(" or ");
+
+        match self.found {
+            Some(Token{ kind: Kind::Invalid, ref value, .. }) => value.clone(),
+            Some(ref token) =>
+                format!("expected {} found {:?} `{}`", expected, token.kind, token.value),
+            None => format!("expected {} found end of file", expected)
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Sintax error: {} at line {}, column {}",
+               self.message(), self.span.line, self.span.column)
+    }
+}
+
+// RuntimeError
+//
+// Represents a failure while interpreting a program. Every kind of failure
+// carries the span of the node that caused it, so embedders can match on
+// what went wrong and still point to where it happened
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeError {
+    UndefinedVariable { name: String, suggestion: Option<String>, span: Span },
+    UndeclaredReassign { name: String, suggestion: Option<String>, span: Span },
+    ImmutableReassign { name: String, span: Span },
+    AlreadyDefined { name: String, span: Span },
+    NotCallable { name: String, span: Span },
+    TypeMismatch { op: String, left: String, right: String, span: Span },
+    InvalidOperand { op: String, operand: String, span: Span },
+    DivisionByZero { span: Span },
+    ArityMismatch { name: String, min: usize, max: Option<usize>, given: usize, span: Span },
+    Overflow { op: String, span: Span },
+    NotFinite { op: String, span: Span },
+    OutsideLoop { keyword: String, span: Span },
+    IndexOutOfBounds { index: i64, length: usize, span: Span },
+    KeyNotFound { key: String, suggestion: Option<String>, span: Span },
+    Unordered { op: String, span: Span },
+    InvalidArgument { name: String, reason: String, span: Span },
+    Panic { message: String, span: Span },
+    Conversion { expected: String, found: String, span: Span },
+    // The names and the spans of the calls that were running, from the
+    // innermost one, which went beyond the maximum depth
+    StackOverflow { depth: usize, trace: Vec<(String, Span)>, span: Span },
+    // The program asked to stop with the given exit status
+    Exit { code: i32, span: Span },
+}
+
+impl RuntimeError {
+    pub fn span(&self) -> Span {
+        match *self {
+            RuntimeError::UndefinedVariable { span, .. } |
+            RuntimeError::UndeclaredReassign { span, .. } |
+            RuntimeError::ImmutableReassign { span, .. } |
+            RuntimeError::AlreadyDefined { span, .. } |
+            RuntimeError::NotCallable { span, .. } |
+            RuntimeError::TypeMismatch { span, .. } |
+            RuntimeError::InvalidOperand { span, .. } |
+            RuntimeError::DivisionByZero { span } |
+            RuntimeError::ArityMismatch { span, .. } |
+            RuntimeError::Overflow { span, .. } |
+            RuntimeError::NotFinite { span, .. } |
+            RuntimeError::OutsideLoop { span, .. } |
+            RuntimeError::IndexOutOfBounds { span, .. } |
+            RuntimeError::KeyNotFound { span, .. } |
+            RuntimeError::Unordered { span, .. } |
+            RuntimeError::InvalidArgument { span, .. } |
+            RuntimeError::Panic { span, .. } |
+            RuntimeError::Conversion { span, .. } |
+            RuntimeError::StackOverflow { span, .. } |
+            RuntimeError::Exit { span, .. } => span
+        }
+    }
+
+    // at
+    //
+    // It places the error in the given span, used by the Interpreter for
+    // errors raised by the primitive operations, which don't know the source
+    pub fn at(mut self, location: Span) -> Self {
+        match self {
+            RuntimeError::UndefinedVariable { ref mut span, .. } |
+            RuntimeError::UndeclaredReassign { ref mut span, .. } |
+            RuntimeError::ImmutableReassign { ref mut span, .. } |
+            RuntimeError::AlreadyDefined { ref mut span, .. } |
+            RuntimeError::NotCallable { ref mut span, .. } |
+            RuntimeError::TypeMismatch { ref mut span, .. } |
+            RuntimeError::InvalidOperand { ref mut span, .. } |
+            RuntimeError::DivisionByZero { ref mut span } |
+            RuntimeError::ArityMismatch { ref mut span, .. } |
+            RuntimeError::Overflow { ref mut span, .. } |
+            RuntimeError::NotFinite { ref mut span, .. } |
+            RuntimeError::OutsideLoop { ref mut span, .. } |
+            RuntimeError::IndexOutOfBounds { ref mut span, .. } |
+            RuntimeError::KeyNotFound { ref mut span, .. } |
+            RuntimeError::Unordered { ref mut span, .. } |
+            RuntimeError::InvalidArgument { ref mut span, .. } |
+            RuntimeError::Panic { ref mut span, .. } |
+            RuntimeError::Conversion { ref mut span, .. } |
+            RuntimeError::StackOverflow { ref mut span, .. } |
+            RuntimeError::Exit { ref mut span, .. } => *span = location
+        }
+        self
+    }
+
+    // message
+    //
+    // It describes the error without its location
+    pub fn message(&self) -> String {
+        match *self {
+            RuntimeError::UndefinedVariable { ref name, .. } =>
+                format!("Variable {} doesn't exist in this context", name),
+            RuntimeError::UndeclaredReassign { ref name, .. } =>
+                format!("Value error: variable {} used before declared", name),
+            RuntimeError::ImmutableReassign { ref name, .. } =>
+                format!("Value error: imutable {} was reassigned", name),
+            RuntimeError::AlreadyDefined { ref name, .. } =>
+                format!("Value error: variable {} has already defined", name),
+            RuntimeError::NotCallable { ref name, .. } =>
+                format!("Value error: {} is not callable", name),
+            RuntimeError::TypeMismatch { ref op, ref left, ref right, .. } =>
+                format!("Operation error: unsupported operand types for {}: {} and {}",
+                        op, left, right),
+            RuntimeError::InvalidOperand { ref op, ref operand, .. } =>
+                format!("Operation error: unsupported operand type for {}: {}",
+                        op, operand),
+            RuntimeError::DivisionByZero { .. } =>
+                String::from("Operation error: division by zero"),
+            RuntimeError::ArityMismatch { ref name, min, max, given, .. } => {
+                let expected = match max {
+                    Some(max) if max == min => format!("{}", min),
+                    Some(max) => format!("{} to {}", min, max),
+                    None => format!("at least {}", min)
+                };
+                format!("Value error: {} expects {} argument{} but {} {} given",
+                        name, expected, if max.unwrap_or(min) == 1 { "" } else { "s" },
+                        given, if given == 1 { "was" } else { "were" })
+            },
+            RuntimeError::Overflow { ref op, .. } =>
+                format!("Operation error: integer overflow in {}", op),
+            RuntimeError::NotFinite { ref op, .. } =>
+                format!("Operation error: float result of {} is not a finite number", op),
+            RuntimeError::OutsideLoop { ref keyword, .. } =>
+                format!("Flow error: {} outside of a loop", keyword),
+            RuntimeError::IndexOutOfBounds { index, length, .. } =>
+                format!("Value error: index {} is out of bounds for length {}",
+                        index, length),
+            RuntimeError::KeyNotFound { ref key, .. } =>
+                format!("Value error: key {:?} not found", key),
+            RuntimeError::Unordered { ref op, .. } =>
+                format!("Operation error: NaN can't be compared with {}", op),
+            RuntimeError::InvalidArgument { ref name, ref reason, .. } =>
+                format!("Value error: invalid argument for {}: {}", name, reason),
+            RuntimeError::Panic { ref message, .. } =>
+                format!("Panic: {}", message),
+            RuntimeError::Conversion { ref expected, ref found, .. } =>
+                format!("Value error: expected {} but found {}", expected, found),
+            RuntimeError::StackOverflow { depth, .. } =>
+                format!("Stack overflow: more than {} nested calls", depth),
+            RuntimeError::Exit { code, .. } =>
+                format!("Exit: the program stopped with status {}", code),
+        }
+    }
+
+    // help
+    //
+    // It gives a hint about how to fix the error, when there is one
+    pub fn help(&self) -> Option<String> {
+        match *self {
+            RuntimeError::UndefinedVariable { suggestion: Some(ref similar), .. } |
+            RuntimeError::UndeclaredReassign { suggestion: Some(ref similar), .. } |
+            RuntimeError::KeyNotFound { suggestion: Some(ref similar), .. } =>
+                Some(format!("did you mean `{}`?", similar)),
+            RuntimeError::ImmutableReassign { ref name, .. } =>
+                Some(format!("did you mean `var`? {} was declared with `let`, \
+                              which can't be reassigned", name)),
+            RuntimeError::StackOverflow { ref trace, .. } =>
+                Some(format!("call trace, innermost first: {}", call_trace(trace))),
+            _ => None
+        }
+    }
+}
+
+// call_trace
+//
+// It describes the calls of a stack overflow, showing a call repeated in a
+// row (e.g. by recursion) only once and the first few calls only
+fn call_trace(trace: &[(String, Span)]) -> String {
+    let mut calls: Vec<(&(String, Span), usize)> = vec![];
+    for call in trace {
+        match calls.last_mut() {
+            Some(&mut (last, ref mut times)) if last == call => *times += 1,
+            _ => calls.push((call, 1))
+        }
+    }
+
+    let shown = 5;
+    let mut described: Vec<String> = calls.iter().take(shown)
+        .map(|&(&(ref name, span), times)| match times {
+            1 => format!("{} at {}:{}", name, span.line, span.column),
+            _ => format!("{} at {}:{} ({} times)", name, span.line, span.column, times)
+        })
+        .collect();
+    if calls.len() > shown {
+        let remaining: usize = calls.iter().skip(shown).map(|&(_, times)| times).sum();
+        described.push(format!("{} more", remaining));
+    }
+    described.join(" <- ")
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let span = self.span();
+        if span.is_known() {
+            write!(f, "{} at line {}, column {}", self.message(), span.line, span.column)
+        } else {
+            write!(f, "{}", self.message())
+        }
+    }
+}
+
+// Error
+//
+// Represents anything that can go wrong while reading and running a program
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Syntax(SyntaxError),
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Syntax(ref error) => error.fmt(f),
+            Error::Runtime(ref error) => error.fmt(f)
+        }
+    }
+}
+
+impl From<SyntaxError> for Error {
+    fn from(error: SyntaxError) -> Self {
+        Error::Syntax(error)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(error: RuntimeError) -> Self {
+        Error::Runtime(error)
+    }
+}
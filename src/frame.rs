@@ -1,70 +1,373 @@
-use std::collections::HashMap;
-use primitive::Type;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use primitive::{Type, Closure};
 
+// Binding
+//
+// A variable's storage. The frames of nested scopes, and the closures
+// created in them, share the same bindings, so assigning a variable is
+// seen everywhere it is visible
+pub type Binding = Rc<RefCell<Type>>;
+
+// FrameStack
+//
+// The frames of the running scopes, the innermost on top. Pushing and
+// popping a scope never copies its variables: a frame only holds its own
+// and refers to its parent for the others
 pub struct FrameStack {
-    stack: Vec<Frame>
+    stack: Vec<Rc<Frame>>,
+    // Finished frames that were still referred to when popped
+    unreleased: Vec<Rc<Frame>>
 }
 impl FrameStack {
     pub fn new() -> Self {
-        FrameStack{ stack: vec![Frame::new()] }
+        FrameStack{ stack: vec![Rc::new(Frame::new())], unreleased: vec![] }
     }
     // Frame stack operations
-    pub fn current(&mut self) -> &mut Frame {
+    pub fn current(&self) -> &Rc<Frame> {
         let stack_size = self.stack.len();
-        &mut self.stack[stack_size-1] //mutable last frame
+        &self.stack[stack_size-1] //last frame
+    }
+
+    // global
+    //
+    // The frame of the main program, which is at the bottom of the stack
+    pub fn global(&self) -> &Rc<Frame> {
+        &self.stack[0]
     }
 
     pub fn push(&mut self, block_scope: Frame) {
-        self.stack.push(block_scope)
+        self.stack.push(Rc::new(block_scope))
+    }
+
+    // pop
+    //
+    // It leaves the innermost scope. A function kept in a variable of the
+    // scope it was defined in, e.g. a named function, which sees itself,
+    // holds the frame that holds it, so the frame is released when nothing
+    // else refers to it
+    pub fn pop(&mut self) {
+        if let Some(frame) = release(self.stack.pop().unwrap()) {
+            self.unreleased.push(frame)
+        }
+    }
+
+    // mark
+    //
+    // The point to release the frames popped after it from, see release_since
+    pub fn mark(&self) -> usize {
+        self.unreleased.len()
+    }
+
+    // release_since
+    //
+    // It tries again to release the frames popped after the mark, e.g. once
+    // a call is done with the function defined in its frame that it called
+    // in tail position. The ones still referred to are kept by their users
+    pub fn release_since(&mut self, mark: usize) {
+        for frame in self.unreleased.split_off(mark).into_iter().rev() {
+            release(frame);
+        }
     }
+}
+
+impl Drop for FrameStack {
+    fn drop(&mut self) {
+        while !self.stack.is_empty() {
+            self.pop()
+        }
+        self.release_since(0)
+    }
+}
+
+// release
+//
+// It clears the variables of a frame that is only referred to by the
+// closures in them, breaking the cycle that would keep it from being freed.
+// The frame is given back when something else refers to it
+fn release(frame: Rc<Frame>) -> Option<Rc<Frame>> {
+    let mut closures: Vec<(Rc<Closure>, usize)> = vec![];
+    for variable in frame.variables.borrow().values() {
+        count_closures(&variable.binding.borrow(), &frame, &mut closures);
+    }
+
+    // Each closure is also counted once by the list, and the frame by the
+    // given reference
+    let internal = closures.iter()
+        .all(|&(ref closure, seen)| Rc::strong_count(closure) == seen + 1);
+    if internal && Rc::strong_count(&frame) == closures.len() + 1 {
+        frame.variables.borrow_mut().clear();
+        None
+    } else {
+        Some(frame)
+    }
+}
 
-    pub fn pop(&mut self) -> Frame {
-        let old = self.stack.pop().unwrap();
-        for (k, v) in old.locals.iter() {
-            if self.current().has(k) {
-                self.current().locals.insert(k.clone(),v.clone());
+// count_closures
+//
+// It counts how many times each closure defined in the frame is found in
+// the value, including inside of lists and maps
+fn count_closures(value: &Type, frame: &Rc<Frame>, closures: &mut Vec<(Rc<Closure>, usize)>) {
+    match *value {
+        Type::Func(ref closure) if Rc::ptr_eq(&closure.env, frame) => {
+            match closures.iter_mut().find(|&&mut (ref seen, _)| Rc::ptr_eq(seen, closure)) {
+                Some(&mut (_, ref mut times)) => *times += 1,
+                None => closures.push((closure.clone(), 1))
             }
-        };
-        old
+        },
+        Type::List(ref items) =>
+            items.iter().for_each(|item| count_closures(item, frame, closures)),
+        Type::Map(ref entries) =>
+            entries.values().for_each(|item| count_closures(item, frame, closures)),
+        _ => {}
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug)]
+struct Variable {
+    binding: Binding,
+    mutable: bool,
+    // Builtins are visible but, unlike the program definitions, can be
+    // replaced by a new definition with the same name
+    builtin: bool
+}
+
+// Frame
+//
+// The variables defined in a scope and a link to the scope around it,
+// where the other visible variables are. A function frame links to the
+// frame where the function was defined, and it is where the definitions of
+// the function stop: blocks can't define a name already defined by their
+// function, or by the main program, but functions can shadow any name
+#[derive(Debug)]
 pub struct Frame {
-    pub iparents: HashMap<String, Type>,
-    pub parents: HashMap<String, Type>,
-    pub ilocals: HashMap<String, Type>,
-    pub locals: HashMap<String, Type>,
+    variables: RefCell<HashMap<String, Variable>>,
+    parent: Option<Rc<Frame>>,
+    function: bool
 }
 impl Frame {
     pub fn new() -> Self {
-        Frame {
-            locals: HashMap::new(),
-            parents: HashMap::new(),
-            ilocals: HashMap::new(),
-            iparents: HashMap::new()
-        }
+        Frame { variables: RefCell::new(HashMap::new()), parent: None, function: true }
+    }
+
+    // block
+    //
+    // A frame for a scope inside of the given one, e.g. a block
+    pub fn block(parent: &Rc<Frame>) -> Self {
+        Frame { parent: Some(parent.clone()), function: false, ..Frame::new() }
     }
 
+    // function
+    //
+    // A frame for a call of a function defined in the given scope
+    pub fn function(parent: &Rc<Frame>) -> Self {
+        Frame { parent: Some(parent.clone()), function: true, ..Frame::new() }
+    }
+
+    // parents
+    //
+    // This frame and the ones around it, from the innermost
+    fn parents(&self) -> Parents<'_> {
+        Parents { next: Some(self) }
+    }
+
+    // has
+    //
+    // Whether the name was defined by the running function (or the main
+    // program), in this scope or in the ones around it
     pub fn has(&self, id: &str) -> bool {
-        self.ilocals.contains_key(id) || self.locals.contains_key(id)
+        for frame in self.parents() {
+            if frame.variables.borrow().get(id).is_some_and(|variable| !variable.builtin) {
+                return true
+            }
+            if frame.function {
+                break
+            }
+        }
+        false
+    }
+
+    // names
+    //
+    // It lists every name visible from this frame
+    pub fn names(&self) -> Vec<String> {
+        self.parents()
+            .flat_map(|frame| frame.variables.borrow().keys().cloned().collect::<Vec<String>>())
+            .collect()
+    }
+
+    // bindings
+    //
+    // The variables defined by the program that are visible from this frame,
+    // sorted by name, with their value and whether they are mutable
+    pub fn bindings(&self) -> Vec<(String, Type, bool)> {
+        let mut seen = HashSet::new();
+        let mut bindings = vec![];
+        for frame in self.parents() {
+            for (name, variable) in frame.variables.borrow().iter() {
+                if seen.insert(name.clone()) && !variable.builtin {
+                    let value = variable.binding.borrow().clone();
+                    bindings.push((name.clone(), value, variable.mutable));
+                }
+            }
+        }
+        bindings.sort_by(|left, right| left.0.cmp(&right.0));
+        bindings
     }
 
     pub fn is_imutable(&self, id: &str) -> bool {
-        self.ilocals.contains_key(id)
+        self.lookup(id, |variable| !variable.mutable).unwrap_or(false)
+    }
+
+    pub fn define(&self, id: String, value: Type) {
+        self.insert(id, value, true, false)
+    }
+
+    pub fn define_imutable(&self, id: String, value: Type) {
+        self.insert(id, value, false, false)
+    }
+
+    // define_builtin
+    //
+    // It defines a name that is visible everywhere but, unlike the ones
+    // defined by the program, can be shadowed by a new definition
+    pub fn define_builtin(&self, id: String, value: Type) {
+        self.insert(id, value, false, true)
     }
 
     pub fn get(&self, id: &str) -> Option<Type> {
-        // parenfunctionst
-        if let Some(value) = self.iparents.get(&*id) { return Some(value.clone()) };
-        if let Some(value) = self.parents.get(&*id) { return Some(value.clone()) };
-        // current
-        if let Some(value) = self.ilocals.get(&*id) { return Some(value.clone()) };
-        if let Some(value) = self.locals.get(&*id) {
-            Some(value.clone())
-        } else {
-            None
+        self.binding(id).map(|binding| binding.borrow().clone())
+    }
+
+    // set
+    //
+    // It replaces the value of a visible variable, regardless of it being
+    // mutable. Returns false when there is no such variable
+    pub fn set(&self, id: &str, value: Type) -> bool {
+        match self.binding(id) {
+            Some(binding) => {
+                *binding.borrow_mut() = value;
+                true
+            },
+            None => false
+        }
+    }
+
+    // update
+    //
+    // It changes in place the value of a visible variable, regardless of it
+    // being mutable. Returns None when there is no such variable
+    pub fn update<F, R>(&self, id: &str, change: F) -> Option<R>
+        where F: FnOnce(&mut Type) -> R {
+        self.binding(id).map(|binding| change(&mut binding.borrow_mut()))
+    }
+
+    fn insert(&self, id: String, value: Type, mutable: bool, builtin: bool) {
+        let binding = Rc::new(RefCell::new(value));
+        self.variables.borrow_mut().insert(id, Variable { binding, mutable, builtin });
+    }
+
+    fn lookup<F, R>(&self, id: &str, found: F) -> Option<R>
+        where F: FnOnce(&Variable) -> R {
+        for frame in self.parents() {
+            if let Some(variable) = frame.variables.borrow().get(id) {
+                return Some(found(variable))
+            }
         }
+        None
+    }
+
+    fn binding(&self, id: &str) -> Option<Binding> {
+        self.lookup(id, |variable| variable.binding.clone())
+    }
+}
+
+struct Parents<'a> {
+    next: Option<&'a Frame>
+}
+
+impl<'a> Iterator for Parents<'a> {
+    type Item = &'a Frame;
+
+    fn next(&mut self) -> Option<&'a Frame> {
+        let current = self.next?;
+        self.next = current.parent.as_deref();
+        Some(current)
     }
 }
 
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+    use ast::Node;
+    use frame::{Frame, FrameStack};
+    use primitive::{Type, Closure};
+
+    fn closure(env: &Rc<Frame>) -> Rc<Closure> {
+        Rc::new(Closure::new(vec![], Node::empty(), env.clone()))
+    }
+
+    #[test]
+    fn it_sees_the_variables_of_its_parents() {
+        let global = Rc::new(Frame::new());
+        global.define(String::from("x"), Type::Int(1));
+        let block = Frame::block(&global);
+
+        assert_eq!(Some(Type::Int(1)), block.get("x"));
+        assert!(block.set("x", Type::Int(2)));
+        assert_eq!(Some(Type::Int(2)), global.get("x"));
+    }
+
+    #[test]
+    fn it_stops_definitions_at_the_function() {
+        let global = Rc::new(Frame::new());
+        global.define_imutable(String::from("x"), Type::Int(1));
+        global.define_builtin(String::from("len"), Type::Nil);
+
+        assert!(Frame::block(&global).has("x"));
+        assert!(!Frame::function(&global).has("x"));
+        assert!(!global.has("len"));
+        assert!(global.is_imutable("len"));
+    }
+
+    #[test]
+    fn it_lists_the_visible_bindings_without_builtins() {
+        let global = Rc::new(Frame::new());
+        global.define_imutable(String::from("x"), Type::Int(1));
+        global.define_builtin(String::from("len"), Type::Nil);
+        let block = Frame::block(&global);
+        block.define(String::from("x"), Type::Int(2));
+        block.define(String::from("a"), Type::Nil);
+
+        assert_eq!(vec![
+            (String::from("a"), Type::Nil, true),
+            (String::from("x"), Type::Int(2), true),
+        ], block.bindings());
+    }
+
+    #[test]
+    fn it_frees_a_finished_frame_holding_its_own_functions() {
+        let mut stack = FrameStack::new();
+        stack.push(Frame::function(stack.global()));
+        let frame = Rc::downgrade(stack.current());
+        let func = closure(stack.current());
+        stack.current().define_imutable(String::from("f"), Type::Func(func.clone()));
+        stack.current().define(String::from("fs"), Type::List(vec![Type::Func(func)]));
+
+        stack.pop();
+        assert_eq!(0, frame.strong_count());
+    }
+
+    #[test]
+    fn it_keeps_a_finished_frame_whose_functions_are_still_used() {
+        let mut stack = FrameStack::new();
+        stack.push(Frame::function(stack.global()));
+        let func = closure(stack.current());
+        stack.current().define_imutable(String::from("f"), Type::Func(func.clone()));
+
+        stack.pop();
+        assert_eq!(2, Rc::strong_count(&func));
+        assert!(func.env.get("f").is_some());
+    }
+}
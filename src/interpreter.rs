@@ -1,30 +1,316 @@
-use ast::{Node, Operation};
-use primitive::Type;
+use ast::{Node, Operation, Param, arity};
+use primitive::{Type, Value, Closure, NativeFunc};
 use frame::{Frame, FrameStack};
+use error::{Error, RuntimeError};
+use token::Tokenizer;
+use parser::Parser;
+use token::Span;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use diagnostics::suggest;
+use builtins;
+use compiler::Compiler;
+use vm;
+use std::env;
+use stacker;
+
+// The default maximum amount of nested function calls
+pub const MAX_DEPTH: usize = 1000;
+
+// Evaluating a node needs at least RED_ZONE bytes of native stack, when
+// there is less the stack grows by a new segment of STACK_SEGMENT bytes. So
+// deep recursion is only limited by the maximum call depth
+pub const RED_ZONE: usize = 128 * 1024;
+pub const STACK_SEGMENT: usize = 4 * 1024 * 1024;
+
+// # Unwind
+//
+// The reasons why evaluating a node stops before its end: an error, or a
+// statement that jumps out of the blocks around it up to the function call
+// (return) or the loop (break and continue) that handles it
+#[derive(Debug)]
+enum Unwind {
+    Error(RuntimeError),
+    Return(Type),
+    Break(Span),
+    Continue(Span)
+}
+
+impl Unwind {
+    // into_error
+    //
+    // A jump that was not handled becomes an error, e.g. a break outside of
+    // a loop
+    fn into_error(self) -> RuntimeError {
+        match self {
+            Unwind::Error(error) => error,
+            Unwind::Return(_) => unreachable!("return is handled by function calls"),
+            Unwind::Break(span) =>
+                RuntimeError::OutsideLoop { keyword: String::from("break"), span },
+            Unwind::Continue(span) =>
+                RuntimeError::OutsideLoop { keyword: String::from("continue"), span }
+        }
+    }
+}
+
+// # Tail
+//
+// The result of evaluating the tail of a function body: its value or the
+// call that gives it
+enum Tail {
+    Value(Type),
+    Call(Rc<Closure>, Vec<Type>)
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Unwind {
+        Unwind::Error(error)
+    }
+}
+
+// # Backend
+//
+// How programs are run: walking their tree or compiling them to bytecode
+// for the Vm. Both give the same results, the default is chosen by the
+// RASCAL_BACKEND environment variable (`tree` or `vm`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    Tree,
+    Vm
+}
+
+impl Backend {
+    pub fn from_name(name: &str) -> Option<Backend> {
+        match name {
+            "tree" => Some(Backend::Tree),
+            "vm" => Some(Backend::Vm),
+            _ => None
+        }
+    }
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        env::var("RASCAL_BACKEND").ok()
+            .and_then(|name| Backend::from_name(&name))
+            .unwrap_or(Backend::Tree)
+    }
+}
 
 // # Interpreter
 //
 // Represents the interpreter that is responsible for interpret
-// the Abstracted Sintax Tree generated by the Parser
-
+// the Abstracted Sintax Tree generated by the Parser. It is also the entry
+// point for embedding rascal in Rust programs:
+//```
+//   let mut interpreter = Interpreter::new();
+//   interpreter.set_global("limit", 10);
+//   interpreter.register_fn("double", |args: &[Value]| {
+//       Ok(Value::from(i64::try_from(args[0].clone())? * 2))
+//   });
+//   let result = interpreter.eval("double(limit)")?; // Value::Int(20)
+//```
 pub struct Interpreter {
-    pub stack: FrameStack,
+    stack: FrameStack,
+    backend: Backend,
+    max_depth: usize,
+    // The name and the span of the running calls, the innermost last
+    calls: Vec<(String, Span)>,
+    // Whether the programs can read the environment and exit the process
+    process: bool,
 }
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
 impl Interpreter {
     pub fn new() -> Self {
-        Interpreter {
-            stack: FrameStack::new()
+        Interpreter::with_backend(Backend::default())
+    }
+
+    pub fn with_backend(backend: Backend) -> Self {
+        let interpreter = Interpreter {
+            stack: FrameStack::new(),
+            backend,
+            max_depth: MAX_DEPTH,
+            calls: vec![],
+            process: false
+        };
+        builtins::define(interpreter.scope());
+        interpreter
+    }
+
+    // eval
+    //
+    // It interprets the source code resulting in the value of its last
+    // statement. Definitions are kept for the following calls
+    pub fn eval(&mut self, source: &str) -> Result<Value, Error> {
+        let tokenizer = Tokenizer::new(source.to_string());
+        let tree = Parser::new(tokenizer).parse()?;
+        match self.backend {
+            Backend::Tree => Ok(self.eval_tree(tree)?),
+            Backend::Vm => {
+                let main = Compiler::compile(tree);
+                Ok(vm::run(main, self.stack.global(), self.max_depth)?)
+            }
         }
     }
 
-    fn scope(&mut self) -> &mut Frame {
+    // set_global
+    //
+    // It makes a value visible to the programs run by this interpreter.
+    // Programs can't reassign it but can shadow it
+    pub fn set_global<V: Into<Value>>(&mut self, name: &str, value: V) {
+        self.stack.global().define_builtin(name.to_string(), value.into());
+    }
+
+    // register_fn
+    //
+    // It makes a Rust function callable by the programs run by this
+    // interpreter, with any amount of arguments
+    pub fn register_fn<F>(&mut self, name: &str, func: F)
+        where F: Fn(&[Value]) -> Result<Value, RuntimeError> + 'static {
+        let native = NativeFunc::new(name, 0, None, func);
+        self.set_global(name, Type::NativeFunc(Rc::new(native)));
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    // get_global
+    //
+    // The value of a variable of the main program or of a global given by
+    // the host
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.stack.global().get(name)
+    }
+
+    // names
+    //
+    // Every name visible to the programs, including the builtins
+    pub fn names(&self) -> Vec<String> {
+        self.scope().names()
+    }
+
+    // set_args
+    //
+    // It gives the programs their command line arguments, the list that
+    // `args()` results in
+    pub fn set_args(&mut self, args: Vec<String>) {
+        builtins::define_args(self.stack.global(), args);
+    }
+
+    // allow_process_access
+    //
+    // It gives the programs the builtins that reach the process running
+    // them: `env(name)` to read environment variables and `exit(code)`,
+    // which stops the program with a RuntimeError::Exit. Embedded programs
+    // don't have them unless the host allows it
+    pub fn allow_process_access(&mut self) {
+        self.process = true;
+        builtins::define_process(self.stack.global());
+    }
+
+    pub fn allows_process_access(&self) -> bool {
+        self.process
+    }
+
+    // set_max_depth
+    //
+    // It limits the amount of nested function calls, deeper recursion stops
+    // the program with a stack overflow error
+    pub fn set_max_depth(&mut self, depth: usize) {
+        self.max_depth = depth;
+    }
+
+    // bindings
+    //
+    // The variables defined by the programs run by this interpreter, sorted
+    // by name, with their value and whether they are mutable
+    pub fn bindings(&self) -> Vec<(String, Value, bool)> {
+        self.scope().bindings()
+    }
+
+    fn scope(&self) -> &Frame {
         self.stack.current()
     }
 
+    // similar_name
+    //
+    // It looks for a name in scope similar to the given one
+    fn similar_name(&self, name: &str) -> Option<String> {
+        suggest(name, &self.scope().names())
+    }
+
+    // closure
+    //
+    // It creates a function value that runs in the current scope
+    fn closure(&self, params: Vec<Param>, body: Node) -> Type {
+        let env = self.stack.current().clone();
+        Type::Func(Rc::new(Closure::new(params, body, env)))
+    }
+
+    // index_path
+    //
+    // It splits the target of an index assignment, e.g. `xs[0][i]`, into the
+    // variable (`xs`) and the evaluated indexes from the outermost (`[0, i]`)
+    fn index_path<'a>(&mut self, target: &'a Node) -> Result<(&'a Node, Vec<Type>), Unwind> {
+        let mut indexes = vec![];
+        let mut target = target;
+        while let Operation::Index(ref inner, ref index) = *target.operation {
+            indexes.push(index);
+            target = inner;
+        }
+
+        let mut path = vec![];
+        for index in indexes.into_iter().rev() {
+            path.push(self.eval_node(index)?);
+        }
+        Ok((target, path))
+    }
+
+    // eval_args
+    //
+    // It evaluates the arguments of a call, from left to right
+    fn eval_args(&mut self, args: &[Node]) -> Result<Vec<Type>, Unwind> {
+        let mut values = vec![];
+        for arg in args {
+            values.push(self.eval_node(arg)?);
+        }
+        Ok(values)
+    }
+
+    // bind_params
+    //
+    // It binds the arguments of a call to the function parameters in the
+    // current scope. Missing arguments take the parameter default, which can
+    // refer to the previous parameters, and a rest parameter takes the
+    // remaining ones as a list
+    fn bind_params(&mut self, params: &[Param], values: Vec<Type>) -> Result<(), Unwind> {
+        let mut values = values.into_iter();
+        for param in params {
+            let value = if param.rest {
+                Type::List(values.by_ref().collect())
+            } else {
+                match (values.next(), &param.default) {
+                    (Some(value), _) => value,
+                    (None, Some(default)) => self.eval_node(default)?,
+                    (None, None) => Type::Nil
+                }
+            };
+            self.scope().define(param.name.clone(), value);
+        }
+        Ok(())
+    }
+
     // eval_tree
     //
     // It visits each node evaluating the binary operations returning the
-    // result as String
+    // value of the last statement
     //
     // Example:
     //   The expression 3 * 5 + 5 will produce the follow tree and the result 20.
@@ -43,20 +329,150 @@ impl Interpreter {
     // ---   ---
     // |3|   |5|
     // +-+   +-+
-    pub fn eval(&mut self, tree: Node) -> String {
-        match self.eval_tree(tree) {
-            Ok(result) => result.to_string(),
-            Err(error) => error
+    pub fn eval_tree(&mut self, tree: Node) -> Result<Type, RuntimeError> {
+        let result = match self.eval_node(&tree) {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(unwind) => Err(unwind.into_error())
+        };
+        self.stack.release_since(0);
+        result
+    }
+
+    // enter
+    //
+    // It starts a function call, unless it goes beyond the maximum depth
+    fn enter(&mut self, name: &str, span: Span) -> Result<(), RuntimeError> {
+        if self.calls.len() >= self.max_depth {
+            let mut trace = vec![(name.to_string(), span)];
+            trace.extend(self.calls.iter().rev().cloned());
+            return Err(RuntimeError::StackOverflow { depth: self.max_depth, trace, span })
         }
+        self.calls.push((name.to_string(), span));
+        Ok(())
+    }
+
+    // prepare_call
+    //
+    // It finds the function called by name and evaluates the arguments.
+    // Native functions are called right away, the others result in the
+    // pending call
+    fn prepare_call(&mut self, nodename: &Node, args: &[Node], span: Span)
+        -> Result<Tail, Unwind> {
+        let name = &nodename.value;
+        let closure = match self.scope().get(name) {
+            Some(Type::Func(closure)) => closure,
+            Some(Type::NativeFunc(native)) => {
+                check_arity(name.clone(), (native.min, native.max), args.len(), span)?;
+                let values = self.eval_args(args)?;
+                return (native.func)(&values)
+                    .map(Tail::Value)
+                    .map_err(|error| error.at(span).into())
+            },
+            Some(_) => return Err(RuntimeError::NotCallable {
+                name: name.clone(),
+                span: nodename.span
+            }.into()),
+            None => return Err(RuntimeError::UndefinedVariable {
+                name: name.clone(),
+                suggestion: self.similar_name(name),
+                span: nodename.span
+            }.into())
+        };
+
+        check_arity(name.clone(), arity(&closure.params), args.len(), span)?;
+        let values = self.eval_args(args)?;
+        Ok(Tail::Call(closure, values))
+    }
+
+    // call
+    //
+    // It runs a function with the given arguments. A call in tail position
+    // of its body runs in its place, so it grows neither the native stack
+    // nor the call depth
+    fn call(&mut self, name: &str, closure: Rc<Closure>, values: Vec<Type>, span: Span)
+        -> Result<Type, Unwind> {
+        self.enter(name, span)?;
+        let mark = self.stack.mark();
+        let mut call = (closure, values);
+        let result = loop {
+            let (closure, values) = call;
+            self.stack.push(Frame::function(&closure.env));
+            let result = self.bind_params(&closure.params, values)
+                .and_then(|_| self.eval_tail(&closure.body));
+            self.stack.pop();
+
+            match result {
+                Ok(Tail::Call(closure, values)) => call = (closure, values),
+                Ok(Tail::Value(value)) | Err(Unwind::Return(value)) => break Ok(value),
+                Err(unwind) => break Err(Unwind::Error(unwind.into_error()))
+            }
+        };
+        self.stack.release_since(mark);
+        self.calls.pop();
+        result
     }
 
-    pub fn eval_tree(&mut self, tree: Node) -> Result<Type, String> {
-        let Node{operation, ..} = tree;
-        match *operation.clone() {
-            Operation::Main(statements) => {
+    // eval_tail
+    //
+    // It evaluates a node in tail position of a function body: its last
+    // statement, the branches of a conditional in tail position or the
+    // returned value. A call there is not made but results in the pending
+    // call, which the running call makes in its place
+    fn eval_tail(&mut self, tree: &Node) -> Result<Tail, Unwind> {
+        match *tree.operation {
+            Operation::CallFunc(ref nodename, ref args) =>
+                self.prepare_call(nodename, args, tree.span),
+
+            Operation::IfElse(ref conditional, ref lnode, ref rnode) => {
+                let condition = self.eval_node(conditional)?;
+
+                if truthy(condition) {
+                    self.eval_tail(lnode)
+                } else {
+                    self.eval_tail(rnode)
+                }
+            },
+
+            Operation::Block(ref statements) => {
+                let block_scope = Frame::block(self.stack.current());
+                self.stack.push(block_scope);
+                let mut result = Ok(Tail::Value(Type::Nil));
+                if let Some((last, statements)) = statements.split_last() {
+                    for stm in statements {
+                        if let Err(unwind) = self.eval_node(stm) {
+                            result = Err(unwind);
+                            break
+                        }
+                    }
+                    if result.is_ok() {
+                        result = self.eval_tail(last);
+                    }
+                }
+                self.stack.pop();
+                result
+            },
+
+            Operation::Return(ref node) => self.eval_tail(node),
+
+            _ => self.eval_node(tree).map(Tail::Value)
+        }
+    }
+
+    fn eval_node(&mut self, tree: &Node) -> Result<Type, Unwind> {
+        stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, || self.eval_operation(tree))
+    }
+
+    // eval_operation
+    //
+    // It evaluates a node, which is interrupted by an Unwind on errors and
+    // on the statements that jump out of it
+    fn eval_operation(&mut self, tree: &Node) -> Result<Type, Unwind> {
+        let span = tree.span;
+        match *tree.operation {
+            Operation::Main(ref statements) => {
                 let mut last_stm_return = Ok(Type::Nil);
                 for statement in statements {
-                    last_stm_return = self.eval_tree(statement);
+                    last_stm_return = self.eval_node(statement);
 
                     if last_stm_return.is_err() {
                         break
@@ -65,119 +481,123 @@ impl Interpreter {
                 last_stm_return
             },
 
-            Operation::IfElse(conditional, lnode, rnode) => {
-                let condition = try!(self.eval_tree(conditional));
+            Operation::IfElse(ref conditional, ref lnode, ref rnode) => {
+                let condition = self.eval_node(conditional)?;
 
                 if truthy(condition) {
-                    self.eval_tree(lnode)
+                    self.eval_node(lnode)
                 } else {
-                    self.eval_tree(rnode)
+                    self.eval_node(rnode)
                 }
             },
 
-            Operation::Binary(lnode, operator, rnode) => {
+            Operation::Binary(ref lnode, ref operator, ref rnode) => {
                 binary_operation(
-                    try!(self.eval_tree(lnode)),
+                    self.eval_node(lnode)?,
                     operator,
-                    try!(self.eval_tree(rnode))
-                )
+                    self.eval_node(rnode)?
+                ).map_err(|error| error.at(span).into())
             },
 
-            Operation::Comparison(lnode, operator, rnode) =>
+            Operation::Comparison(ref lnode, ref operator, ref rnode) =>
                 binary_comparison(
-                    try!(self.eval_tree(lnode)),
+                    self.eval_node(lnode)?,
                     operator,
-                    try!(self.eval_tree(rnode))
-                ),
-
-            Operation::CallFunc(nodename, params) => {
-                let func_frame = self.scope().clone();
-                self.stack.push(func_frame);
-
-                let name = nodename.value;
-                // println!("calling {}", name);
-                if let Some(Type::Func(fparams, block)) = self.scope().get(&*name).clone() {
-                    for (pname, pvalue) in fparams.iter().zip(params.iter()) {
-                        let value = try!(self.eval_tree(pvalue.clone()));
-                        self.scope().locals.insert(pname.clone().value, value);
-                    }
+                    self.eval_node(rnode)?
+                ).map_err(|error| error.at(span).into()),
 
-                    self.eval_tree(block)
-                } else {
-                    return Err(format!("Value error: {} is not callable", name))
+            Operation::CallFunc(ref nodename, ref args) => {
+                match self.prepare_call(nodename, args, span)? {
+                    Tail::Value(value) => Ok(value),
+                    Tail::Call(closure, values) => self.call(&nodename.value, closure, values, span)
                 }
             },
 
-            Operation::DefineFunc(lnode, func) => {
-                let name = lnode.value;
-                if self.scope().has(&*name) {
-                    return Err(format!("Value error: variable {} has already defined.", name))
+            Operation::DefineFunc(ref lnode, ref params, ref block) => {
+                let name = lnode.value.clone();
+                if self.scope().has(&name) {
+                    return Err(RuntimeError::AlreadyDefined { name, span: lnode.span }.into())
                 }
 
-                self.scope().ilocals.insert(name, func);
+                // The name is defined before capturing the scope, so the
+                // function can call itself
+                self.scope().define_imutable(name.clone(), Type::Nil);
+                let func = self.closure(params.clone(), block.clone());
+                self.scope().set(&name, func);
                 Ok(Type::Nil)
             },
 
-            Operation::DefineImut(lnode, rnode) => {
-                let name = lnode.value;
-                let value = try!(self.eval_tree(rnode));
+            Operation::Function(ref params, ref block) =>
+                Ok(self.closure(params.clone(), block.clone())),
+
+            Operation::DefineImut(ref lnode, ref rnode) => {
+                let name = lnode.value.clone();
+                let value = self.eval_node(rnode)?;
 
-                if self.scope().has(&*name) {
-                    return Err(format!("Value error: variable {} has already defined.", name))
+                if self.scope().has(&name) {
+                    return Err(RuntimeError::AlreadyDefined { name, span: lnode.span }.into())
                 }
 
-                self.scope().ilocals.insert(name, value.clone());
+                self.scope().define_imutable(name, value);
                 Ok(Type::Nil)
             },
 
-            Operation::DefineVar(lnode, rnode) => {
-                let name = lnode.value;
+            Operation::DefineVar(ref lnode, ref rnode) => {
+                let name = lnode.value.clone();
 
-                if self.scope().has(&*name) {
-                    return Err(format!("Value error: variable {} has already defined.", name))
+                if self.scope().has(&name) {
+                    return Err(RuntimeError::AlreadyDefined { name, span: lnode.span }.into())
                 }
 
-                let value = try!(self.eval_tree(rnode));
+                let value = self.eval_node(rnode)?;
 
-                self.scope().locals.insert(name, value.clone());
+                self.scope().define(name, value.clone());
                 Ok(value)
             },
 
-            Operation::ReAssign(lnode, rnode) => {
-                let name = lnode.value;
+            Operation::ReAssign(ref lnode, ref rnode) => {
+                let name = lnode.value.clone();
 
-                if !self.scope().has(&*name) {
-                    return Err(format!("Value error: variable {} used before declared.", name))
+                if self.scope().get(&name).is_none() {
+                    let suggestion = self.similar_name(&name);
+                    return Err(RuntimeError::UndeclaredReassign {
+                        name, suggestion, span: lnode.span
+                    }.into())
                 }
 
-                if self.scope().is_imutable(&*name) {
-                    return Err(format!("Value error: imutable {} was reassigned.", name))
+                if self.scope().is_imutable(&name) {
+                    return Err(RuntimeError::ImmutableReassign { name, span: lnode.span }.into())
                 }
 
-                let value = try!(self.eval_tree(rnode));
+                let value = self.eval_node(rnode)?;
 
-                self.scope().locals.insert(name, value.clone());
+                self.scope().set(&name, value.clone());
                 Ok(value)
             },
 
-            Operation::NegUnary(node) => {
-                unary_operation("-", try!(self.eval_tree(node)))
+            Operation::Unary(ref operator, ref node) => {
+                unary_operation(operator, self.eval_node(node)?)
+                    .map_err(|error| error.at(span).into())
             },
 
-            Operation::Return(node) => self.eval_tree(node),
+            Operation::Return(ref node) => Err(Unwind::Return(self.eval_node(node)?)),
 
-            Operation::Print(node) => {
-                let result = try!(self.eval_tree(node));
-                println!("{}", result.to_string());
+            Operation::Break => Err(Unwind::Break(span)),
+
+            Operation::Continue => Err(Unwind::Continue(span)),
+
+            Operation::Print(ref node) => {
+                let result = self.eval_node(node)?;
+                println!("{}", result);
                 Ok(Type::Nil)
             },
 
-            Operation::Block(statements) => {
-                let copy_scope = self.scope().clone();
-                self.stack.push(copy_scope);
+            Operation::Block(ref statements) => {
+                let block_scope = Frame::block(self.stack.current());
+                self.stack.push(block_scope);
                 let mut last_stm_return = Ok(Type::Nil);
                 for stm in statements {
-                    last_stm_return = self.eval_tree(stm.clone());
+                    last_stm_return = self.eval_node(stm);
 
                     if last_stm_return.is_err() {
                         break
@@ -187,74 +607,180 @@ impl Interpreter {
                 last_stm_return
             },
 
-            Operation::Loop(conditional, block) => {
-                let mut condition = try!(self.eval_tree(conditional.clone()));
+            Operation::Loop(ref conditional, ref block) => {
+                let mut condition = self.eval_node(conditional)?;
 
                 while truthy(condition) {
-                    let _ = try!(self.eval_tree(block.clone()));
-                    condition = try!(self.eval_tree(conditional.clone()));
+                    match self.eval_node(block) {
+                        Ok(_) | Err(Unwind::Continue(_)) => (),
+                        Err(Unwind::Break(_)) => break,
+                        Err(unwind) => return Err(unwind)
+                    }
+                    condition = self.eval_node(conditional)?;
                 }
 
                 Ok(Type::Nil)
             },
 
-            Operation::Identifier(name) => {
-                if let Some(value) = self.scope().get(&*name) {
+            Operation::ForIn(ref variable, ref iterable, ref block) => {
+                let name = &variable.value;
+                if self.scope().has(name) {
+                    return Err(RuntimeError::AlreadyDefined {
+                        name: name.clone(),
+                        span: variable.span
+                    }.into())
+                }
+
+                let items = self.eval_node(iterable)?
+                    .iterate()
+                    .map_err(|error| error.at(span))?;
+
+                for item in items {
+                    let item_scope = Frame::block(self.stack.current());
+                    item_scope.define_imutable(name.clone(), item);
+                    self.stack.push(item_scope);
+                    let result = self.eval_node(block);
+                    self.stack.pop();
+
+                    match result {
+                        Ok(_) | Err(Unwind::Continue(_)) => (),
+                        Err(Unwind::Break(_)) => break,
+                        Err(unwind) => return Err(unwind)
+                    }
+                }
+
+                Ok(Type::Nil)
+            },
+
+            Operation::Identifier(ref name) => {
+                if let Some(value) = self.scope().get(name) {
                     Ok(value)
                 } else {
-                    Err(format!("Variable {} doesn't exist in this context", name))
+                    let suggestion = self.similar_name(name);
+                    Err(RuntimeError::UndefinedVariable { name: name.clone(), suggestion, span }.into())
                 }
             },
 
-            Operation::Constant(var) => Ok(var),
+            Operation::Constant(ref var) => Ok(var.clone()),
+
+            Operation::List(ref items) => {
+                let mut values = vec![];
+                for item in items {
+                    values.push(self.eval_node(item)?);
+                }
+                Ok(Type::List(values))
+            },
+
+            Operation::Map(ref entries) => {
+                let mut values = BTreeMap::new();
+                for (key, value) in entries {
+                    values.insert(key.clone(), self.eval_node(value)?);
+                }
+                Ok(Type::Map(values))
+            },
+
+            Operation::Index(ref target, ref index) => {
+                let target = self.eval_node(target)?;
+                let index = self.eval_node(index)?;
+                target.index(&index).map_err(|error| error.at(span).into())
+            },
+
+            Operation::IndexAssign(ref target, ref rnode) => {
+                let (lnode, path) = self.index_path(target)?;
+                let name = lnode.value.clone();
+
+                if self.scope().get(&name).is_none() {
+                    let suggestion = self.similar_name(&name);
+                    return Err(RuntimeError::UndeclaredReassign {
+                        name, suggestion, span: lnode.span
+                    }.into())
+                }
+
+                if self.scope().is_imutable(&name) {
+                    return Err(RuntimeError::ImmutableReassign { name, span: lnode.span }.into())
+                }
+
+                let value = self.eval_node(rnode)?;
+                self.scope()
+                    .update(&name, |current| current.set_index(&path, value.clone()))
+                    .unwrap_or(Ok(()))
+                    .map_err(|error| error.at(span))?;
+                Ok(value)
+            },
 
             _ => Ok(Type::Nil)
         }
     }
 }
 
+// check_arity
+//
+// It validates the amount of arguments given to a call
+pub fn check_arity(name: String, (min, max): (usize, Option<usize>), given: usize, span: Span)
+    -> Result<(), RuntimeError> {
+    if given < min || max.is_some_and(|max| given > max) {
+        return Err(RuntimeError::ArityMismatch { name, min, max, given, span })
+    }
+    Ok(())
+}
+
 // unary_operation
-// Resolves the unary operations Example: --1 == 1, 1++-1==0
-fn unary_operation(operator: &str, operand: Type) -> Result<Type, String> {
-    match (operator , operand.clone()) {
-        ("+", Type::Int(val)) => Ok(Type::Int(val)),
-        ("-", Type::Int(val)) => Ok(Type::Int(-val)),
-        _ => Err(format!("Operation error: invalid operation {:?}{:?}",
-                    operator, operand))
+// Resolves the unary operations Example: --1 == 1, 1++-1==0, not true
+pub fn unary_operation(operator: &str, operand: Type) -> Result<Type, RuntimeError> {
+    match (operator, operand) {
+        ("not", operand) | ("!", operand) => Ok(Type::Bool(!operand.as_bool())),
+        ("+", Type::Float(val)) => Ok(Type::Float(val)),
+        ("-", Type::Float(val)) => Ok(Type::Float(-val)),
+        ("+", operand) if operand.type_name() == "Int" => Ok(operand),
+        ("-", operand) if operand.type_name() == "Int" => Type::Int(0) - operand,
+        (_, operand) => Err(RuntimeError::InvalidOperand {
+            op: operator.to_string(),
+            operand: operand.type_name().to_string(),
+            span: Span::default()
+        })
     }
 }
 
 // binary_operation
 // Resolve binary expression for the given left, operator and right operand
-fn binary_operation(left: Type, operator: String, right: Type) -> Result<Type, String> {
-    match (left, operator.as_ref(), right) {
-        (r, "*", l) => Ok(r * l),
-        (r, "/", l) => Ok(r / l),
-        (r, "+", l) => Ok(r + l),
-        (r, "-", l) => Ok(r - l),
-        (r, "%", l) => Ok(r % l),
-        _ => Err(format!("Sintax error: invalid operator {:?}", operator))
+pub fn binary_operation(left: Type, operator: &str, right: Type) -> Result<Type, RuntimeError> {
+    match operator {
+        "*" => left * right,
+        "/" => left / right,
+        "+" => left + right,
+        "-" => left - right,
+        "%" => left % right,
+        _ => Err(mismatch(operator, &left, &right))
     }
 }
 
-// binary_operation
-// Resolve binary expression for the given left, operator and right operand
-fn binary_comparison(left: Type, operator: String, right: Type) -> Result<Type, String> {
-    match operator.as_ref() {
+// binary_comparison
+// Resolve comparison expression for the given left, operator and right operand
+pub fn binary_comparison(left: Type, operator: &str, right: Type) -> Result<Type, RuntimeError> {
+    match operator {
         "==" => Ok(Type::Bool(left == right)),
         "!=" => Ok(Type::Bool(left != right)),
-        ">"  => Ok(Type::Bool(left >  right)),
-        "<"  => Ok(Type::Bool(left <  right)),
+        ">"  => Ok(Type::Bool(left.compare(&right, ">")? == Ordering::Greater)),
+        "<"  => Ok(Type::Bool(left.compare(&right, "<")? == Ordering::Less)),
+        ">=" => Ok(Type::Bool(left.compare(&right, ">=")? != Ordering::Less)),
+        "<=" => Ok(Type::Bool(left.compare(&right, "<=")? != Ordering::Greater)),
         "||"|"or"  => Ok(Type::Bool(right.as_bool() || left.as_bool())),
         "&&"|"and" => Ok(Type::Bool(right.as_bool() && left.as_bool())),
-        _ =>
-            Err(format!("Sintax error: invalid operation {:?}{:?}{:?}",
-                        right, operator, left))
+        _ => Err(mismatch(operator, &left, &right))
+    }
+}
+
+fn mismatch(operator: &str, left: &Type, right: &Type) -> RuntimeError {
+    RuntimeError::TypeMismatch {
+        op: operator.to_string(),
+        left: left.type_name().to_string(),
+        right: right.type_name().to_string(),
+        span: Span::default()
     }
 }
 
 fn truthy(condition: Type) -> bool {
-    binary_comparison(condition, String::from("=="), Type::Bool(true)).unwrap().as_bool()
+    condition == Type::Bool(true)
 }
 
 
@@ -262,16 +788,25 @@ fn truthy(condition: Type) -> bool {
 mod test {
 
     use token::{Token, Kind, Tokenizer};
-    use interpreter::Interpreter;
+    use std::rc::Rc;
+    use interpreter::{Interpreter, Backend};
     use parser::Parser;
+    use primitive::Type;
     use ast::Node;
 
+    fn eval(tree: Node) -> String {
+        match Interpreter::new().eval_tree(tree) {
+            Ok(result) => result.to_string(),
+            Err(error) => error.to_string()
+        }
+    }
+
     #[test]
     fn it_eval_tree_leaf() {
         let token = Token::build(Kind::Integer, String::from("10"));
         let leaf = Node::constant(token);
 
-        assert_eq!("10", Interpreter::new().eval(leaf))
+        assert_eq!("10", eval(leaf))
     }
 
     #[test]
@@ -282,7 +817,7 @@ mod test {
         let right = Node::constant(Token::build(Kind::Integer, String::from("5")));
         let node = Node::binary(left, operator.value, right);
 
-        assert_eq!("8", Interpreter::new().eval(node))
+        assert_eq!("8", eval(node))
     }
 
     #[test]
@@ -297,7 +832,7 @@ mod test {
         let sumright = Node::constant(Token::build(Kind::Integer, String::from("5")));
         let sumnode = Node::binary(plusnode, operator.value, sumright);
 
-        assert_eq!("20", Interpreter::new().eval(sumnode))
+        assert_eq!("20", eval(sumnode))
     }
 
     #[test]
@@ -311,7 +846,7 @@ mod test {
         let left = Node::constant(Token::build(Kind::Integer, String::from("2")));
         let sumnode = Node::binary(left, operator.value, unarynode);
 
-        assert_eq!("4", Interpreter::new().eval(sumnode))
+        assert_eq!("4", eval(sumnode))
     }
 
     #[test]
@@ -320,7 +855,7 @@ mod test {
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
 
-        assert_eq!("6", Interpreter::new().eval(parser.parse()));
+        assert_eq!("6", eval(parser.parse().unwrap()));
     }
 
     #[test]
@@ -329,7 +864,7 @@ mod test {
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
 
-        assert_eq!("4", Interpreter::new().eval(parser.parse()));
+        assert_eq!("4", eval(parser.parse().unwrap()));
     }
 
     #[test]
@@ -338,7 +873,7 @@ mod test {
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
 
-        assert_eq!("10", Interpreter::new().eval(parser.parse()));
+        assert_eq!("10", eval(parser.parse().unwrap()));
     }
 
     #[test]
@@ -347,7 +882,7 @@ mod test {
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
 
-        assert_eq!("2", Interpreter::new().eval(parser.parse()));
+        assert_eq!("2", eval(parser.parse().unwrap()));
     }
 
     #[test]
@@ -356,7 +891,7 @@ mod test {
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
 
-        assert_eq!("0", Interpreter::new().eval(parser.parse()));
+        assert_eq!("0", eval(parser.parse().unwrap()));
     }
 
     #[test]
@@ -365,7 +900,7 @@ mod test {
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
 
-        assert_eq!("10", Interpreter::new().eval(parser.parse()));
+        assert_eq!("10", eval(parser.parse().unwrap()));
     }
 
     #[test]
@@ -374,7 +909,7 @@ mod test {
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
 
-        assert_eq!("3", Interpreter::new().eval(parser.parse()));
+        assert_eq!("3", eval(parser.parse().unwrap()));
     }
 
     #[test]
@@ -383,7 +918,7 @@ mod test {
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
 
-        assert_eq!("10", Interpreter::new().eval(parser.parse()));
+        assert_eq!("10", eval(parser.parse().unwrap()));
     }
 
     #[test]
@@ -392,7 +927,7 @@ mod test {
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
 
-        assert_eq!("5", Interpreter::new().eval(parser.parse()));
+        assert_eq!("5", eval(parser.parse().unwrap()));
     }
 
     #[test]
@@ -401,7 +936,7 @@ mod test {
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
 
-        assert_eq!("false", Interpreter::new().eval(parser.parse()));
+        assert_eq!("false", eval(parser.parse().unwrap()));
     }
 
     #[test]
@@ -410,7 +945,7 @@ mod test {
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
 
-        assert_eq!("true", Interpreter::new().eval(parser.parse()));
+        assert_eq!("true", eval(parser.parse().unwrap()));
     }
 
     #[test]
@@ -419,7 +954,7 @@ mod test {
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
 
-        assert_eq!("false", Interpreter::new().eval(parser.parse()));
+        assert_eq!("false", eval(parser.parse().unwrap()));
     }
 
 
@@ -428,8 +963,7 @@ mod test {
         let text = "var x = 10; return x";
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
-        let mut interpreter = Interpreter::new();
-        let result = interpreter.eval(parser.parse());
+        let result = eval(parser.parse().unwrap());
         assert_eq!("10", result);
     }
 
@@ -438,8 +972,7 @@ mod test {
         let text = "let x = 10; var y = x + 5; return y";
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
-        let mut interpreter = Interpreter::new();
-        let result = interpreter.eval(parser.parse());
+        let result = eval(parser.parse().unwrap());
 
         assert_eq!("15", result);
     }
@@ -449,8 +982,7 @@ mod test {
         let text = "{ let two = fn [] { return 2 }; two() }";
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
-        let mut interpreter = Interpreter::new();
-        let result = interpreter.eval(parser.parse());
+        let result = eval(parser.parse().unwrap());
 
         assert_eq!("2", result);
     }
@@ -460,8 +992,7 @@ mod test {
         let text = "{ let add = fn [x] { return x + 2 }; add(2) }";
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
-        let mut interpreter = Interpreter::new();
-        let result = interpreter.eval(parser.parse());
+        let result = eval(parser.parse().unwrap());
 
         assert_eq!("4", result);
     }
@@ -471,10 +1002,111 @@ mod test {
         let text = "{ let add = fn [x,y,z] { return x + y + z }; add(2,1,2) }";
         let tokenizer = Tokenizer::new(String::from(text));
         let mut parser = Parser::new(tokenizer);
-        let mut interpreter = Interpreter::new();
-        let result = interpreter.eval(parser.parse());
+        let result = eval(parser.parse().unwrap());
 
         assert_eq!("5", result);
     }
 
+    #[test]
+    fn it_concatenates_strings() {
+        let text = "let x = \"foo\"; x + \"bar\"";
+        let tokenizer = Tokenizer::new(String::from(text));
+        let mut parser = Parser::new(tokenizer);
+
+        assert_eq!("foobar", eval(parser.parse().unwrap()));
+    }
+
+    #[test]
+    fn it_compares_strings() {
+        let text = "\"abc\" < \"abd\" and \"a\" != \"b\" and \"a\" == \"a\"";
+        let tokenizer = Tokenizer::new(String::from(text));
+        let mut parser = Parser::new(tokenizer);
+
+        assert_eq!("true", eval(parser.parse().unwrap()));
+    }
+
+    #[test]
+    fn it_chains_operators_of_same_precedence() {
+        let text = "2 * 3 * 4 - 10 - 4 + 100 / 10 / 5";
+        let tokenizer = Tokenizer::new(String::from(text));
+        let mut parser = Parser::new(tokenizer);
+
+        assert_eq!("12", eval(parser.parse().unwrap()));
+    }
+
+    #[test]
+    fn it_compares_after_arithmetic() {
+        let text = "1 + 2 == 3 and 2 * 2 != 5 and 10 % 3 == 1";
+        let tokenizer = Tokenizer::new(String::from(text));
+        let mut parser = Parser::new(tokenizer);
+
+        assert_eq!("true", eval(parser.parse().unwrap()));
+    }
+
+    #[test]
+    fn it_accept_greater_or_equal_and_lesser_or_equal() {
+        let text = "3 >= 3 and 2 >= 1 and 1 <= 1 and not (2 <= 1)";
+        let tokenizer = Tokenizer::new(String::from(text));
+        let mut parser = Parser::new(tokenizer);
+
+        assert_eq!("true", eval(parser.parse().unwrap()));
+    }
+
+    #[test]
+    fn it_negates_booleans() {
+        let text = "!false == not not true";
+        let tokenizer = Tokenizer::new(String::from(text));
+        let mut parser = Parser::new(tokenizer);
+
+        assert_eq!("true", eval(parser.parse().unwrap()));
+    }
+
+    #[test]
+    fn it_negates_right_after_other_operators() {
+        let text = "let x = true; let y = false; [!!x, x==!y, x!=!y]";
+        let tokenizer = Tokenizer::new(String::from(text));
+        let mut parser = Parser::new(tokenizer);
+
+        assert_eq!("[true, true, false]", eval(parser.parse().unwrap()));
+    }
+
+    #[test]
+    fn it_binds_and_tighter_than_or() {
+        let text = "true or false and false";
+        let tokenizer = Tokenizer::new(String::from(text));
+        let mut parser = Parser::new(tokenizer);
+
+        assert_eq!("true", eval(parser.parse().unwrap()));
+    }
+
+    #[test]
+    fn it_keeps_the_sign_of_unary_plus() {
+        let text = "+2 - -3";
+        let tokenizer = Tokenizer::new(String::from(text));
+        let mut parser = Parser::new(tokenizer);
+
+        assert_eq!("5", eval(parser.parse().unwrap()));
+    }
+
+    #[test]
+    fn it_frees_the_frames_of_finished_calls_defining_functions() {
+        let mut interpreter = Interpreter::with_backend(Backend::Tree);
+        let text = "let outer = fn [] { let down = fn [n] { if n == 0 { 0 else down(n - 1) } }; down(3) };
+                    outer();
+                    outer()";
+        assert_eq!(Type::Int(0), interpreter.eval(text).unwrap());
+
+        // The stack and `outer`, without the parents of leaked call frames
+        assert_eq!(2, Rc::strong_count(interpreter.stack.global()));
+    }
+
+    #[test]
+    fn it_keeps_the_frames_of_finished_calls_whose_functions_are_returned() {
+        let mut interpreter = Interpreter::with_backend(Backend::Tree);
+        let text = "let outer = fn [] { let down = fn [n] { if n == 0 { 0 else down(n - 1) } }; down };
+                    let f = outer();
+                    f(3)";
+
+        assert_eq!(Type::Int(0), interpreter.eval(text).unwrap());
+    }
 }
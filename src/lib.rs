@@ -1,14 +1,38 @@
+#[cfg(feature = "bigint")]
+extern crate num_bigint;
+#[cfg(feature = "bigint")]
+extern crate num_traits;
+extern crate stacker;
+
 mod token;
 mod interpreter;
 mod ast;
 mod parser;
 mod primitive;
 mod frame;
+mod builtins;
+mod compiler;
+mod vm;
+pub mod error;
+pub mod diagnostics;
 pub mod repl;
 
+pub use token::{Kind, Span, Token};
+pub use interpreter::{Interpreter, Backend};
+pub use primitive::{Type, Value};
+use error::Error;
+
+// run
+//
+// It interprets the source code resulting in its last statement value, or in
+// the Error that stopped it
+pub fn run(source: String) -> Result<String, Error> {
+    Ok(Interpreter::new().eval(&source)?.to_string())
+}
+
 pub fn eval(source: String) -> String {
-    let tokenizer = token::Tokenizer::new(source);
-    let mut parser = parser::Parser::new(tokenizer);
-    let mut interpreter = interpreter::Interpreter::new();
-    interpreter.eval(parser.parse())
+    match run(source) {
+        Ok(result) => result,
+        Err(error) => error.to_string()
+    }
 }
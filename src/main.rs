@@ -1,40 +1,80 @@
-extern crate rustc_serialize;
 extern crate docopt;
 extern crate rascal;
+extern crate rustyline;
 
-use std::io::{self};
-use std::io::prelude::*;
+use std::io::{self, Read, Write};
+use std::borrow::Cow;
+use std::env;
 use std::fs::File;
-use std::panic;
+use std::path::PathBuf;
+use std::process;
+use std::time::Instant;
 
-use rascal::repl;
+use rascal::{repl, Backend, Interpreter, Value};
+use rascal::diagnostics::Diagnostic;
+use rascal::error::{Error, RuntimeError};
 
-use docopt::Docopt;
+use docopt::{Docopt, ArgvMap};
+use rustyline::{Context, Editor, Helper};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::{Hint, Hinter};
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
 
-const VERSION: &'static str = env!("CARGO_PKG_VERSION");
-const USAGE: &'static str = "
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const USAGE: &str = "
 Rascal.
 
 Usage:
-  rascal
-  rascal <source>
+  rascal [options]
+  rascal [options] run <source> [<args>...]
+  rascal [options] <source> [<args>...]
+  rascal [options] -e <expression>
   rascal (-h | --help)
   rascal (-v | --version)
 
+Runs the program in the <source> file, or read from stdin when it is `-`,
+or the given <expression>, showing its resulting value. The program gets
+the <args> from `args()` and can stop with an exit status with `exit(code)`.
+Without a program it opens the REPL.
+
 Options:
-  -r --repl         Opens the REPL.
+  -e <expression>   Runs the given program.
+  -r --repl         Opens the REPL, after running the program with its
+                    definitions.
   -h --help         Shows this message.
   -v --version      Shows version.
-  --verbose         Use verbose output.
+  --verbose         Shows the backend and the time running the program.
+  --backend=<name>  Runs programs walking their tree (tree) or compiled
+                    to bytecode (vm). Defaults to $RASCAL_BACKEND or tree.
 ";
 
-#[derive(Debug, RustcDecodable)]
+#[derive(Debug)]
 pub struct Args {
-    pub arg_source: Vec<String>,
+    pub arg_source: String,
+    pub arg_args: Vec<String>,
+    pub flag_e: String,
     pub flag_r: bool,
     pub flag_h: bool,
     pub flag_v: bool,
-
+    pub flag_verbose: bool,
+    pub flag_backend: String,
+}
+impl Args {
+    fn from(argv: ArgvMap) -> Self {
+        Args {
+            arg_source: argv.get_str("<source>").to_string(),
+            arg_args: argv.get_vec("<args>").iter().map(|s| s.to_string()).collect(),
+            flag_e: argv.get_str("-e").to_string(),
+            flag_r: argv.get_bool("--repl"),
+            flag_h: argv.get_bool("--help"),
+            flag_v: argv.get_bool("--version"),
+            flag_verbose: argv.get_bool("--verbose"),
+            flag_backend: argv.get_str("--backend").to_string(),
+        }
+    }
 }
 
 // REPL
@@ -42,28 +82,202 @@ pub struct Args {
 // accepts a file to interpret
 fn main() {
     let args: Args = Docopt::new(USAGE)
-        .and_then(|dopt| dopt.decode()).unwrap_or_else(|e| e.exit());
-
-    print!(">>");
-    io::stdout().flush().ok().expect("Ops... Something went wrong. :(");
-    match args {
-        Args { flag_v: true, ..} => println!("{}", VERSION),
-        Args { ref arg_source, ..} if arg_source.len() > 0 => {
-            let mut f = File::open(&arg_source[0]).unwrap();
-            let mut source_code = String::new();
-            let _ = f.read_to_string(&mut source_code);
-            print!("{}", rascal::eval(source_code));
+        .and_then(|dopt| dopt.parse()).map(Args::from).unwrap_or_else(|e| e.exit());
+    let backend = match args.flag_backend.as_ref() {
+        "" => Backend::default(),
+        name => Backend::from_name(name).unwrap_or_else(|| {
+            eprintln!("Unknown backend `{}`, expected tree or vm", name);
+            process::exit(1)
+        })
+    };
+
+    if args.flag_v {
+        println!("{}", VERSION);
+        return
+    }
+
+    let program = match args {
+        Args { ref flag_e, .. } if !flag_e.is_empty() =>
+            Some((String::from("<expression>"), flag_e.clone())),
+        Args { ref arg_source, .. } if arg_source == "-" =>
+            Some((String::from("<stdin>"), read_program(io::stdin(), "stdin"))),
+        Args { ref arg_source, .. } if !arg_source.is_empty() => {
+            let file = File::open(arg_source).unwrap_or_else(|error| {
+                eprintln!("Can't read {}: {}", arg_source, error);
+                process::exit(1)
+            });
+            Some((arg_source.clone(), read_program(file, arg_source)))
+        },
+        _ => None
+    };
+
+    // Unlike embedded ones, the programs run by rascal are scripts of the
+    // command line, which can read its environment and exit
+    let mut interpreter = Interpreter::with_backend(backend);
+    interpreter.allow_process_access();
+
+    let (name, source_code) = match program {
+        Some(program) => program,
+        None => return run_repl(repl::Repl::with_interpreter(interpreter))
+    };
+
+    interpreter.set_args(args.arg_args.clone());
+    let started = Instant::now();
+    let result = interpreter.eval(&source_code);
+    if args.flag_verbose {
+        eprintln!("Ran {} with the {:?} backend in {:?}", name, backend, started.elapsed());
+    }
+
+    let succeeded = match result {
+        Err(Error::Runtime(RuntimeError::Exit { code, .. })) => exit(code),
+        Ok(Value::Nil) => true,
+        Ok(result) => {
+            println!("{}", result);
+            true
         },
-        _ => {
-            let stdin = io::stdin();
-            let mut repl = repl::Repl::new();
-            while let Some(line) = stdin.lock().lines().next() {
-                if let Ok(source_code) = line {
-                    println!("{}", repl.eval(source_code));
+        Err(error) => {
+            eprint!("{}", Diagnostic::from(error).render_to_terminal(&name, &source_code));
+            false
+        }
+    };
+
+    if args.flag_r {
+        run_repl(repl::Repl::with_interpreter(interpreter))
+    } else if !succeeded {
+        process::exit(1)
+    }
+}
+
+// exit
+//
+// It leaves with the status given by the program to `exit`, after showing
+// what the program printed so far
+fn exit(code: i32) -> ! {
+    let _ = io::stdout().flush();
+    process::exit(code)
+}
+
+// read_program
+//
+// It reads the whole program, leaving when it can't
+fn read_program<R: Read>(mut input: R, name: &str) -> String {
+    let mut source_code = String::new();
+    if let Err(error) = input.read_to_string(&mut source_code) {
+        eprintln!("Can't read {}: {}", name, error);
+        process::exit(1)
+    }
+    source_code
+}
+
+// history_path
+//
+// The file keeping the lines typed in the REPL between sessions
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".rascal_history"))
+}
+
+// Session
+//
+// The REPL session as the line editor helper, which completes the words
+// with Tab and shows the parameters of the function being called
+struct Session {
+    repl: repl::Repl
+}
+
+impl Completer for Session {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, position: usize, _: &Context)
+        -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.repl.complete(line, position))
+    }
+}
+
+// The hint only shows the parameters, it is not completed
+struct Params(String);
+
+impl Hint for Params {
+    fn display(&self) -> &str {
+        &self.0
+    }
+
+    fn completion(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl Hinter for Session {
+    type Hint = Params;
+
+    fn hint(&self, line: &str, position: usize, _: &Context) -> Option<Params> {
+        self.repl.hint(line, position).map(Params)
+    }
+}
+
+impl Highlighter for Session {
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(format!("\x1b[2m{}\x1b[0m", hint))
+    }
+}
+
+impl Validator for Session {}
+
+impl Helper for Session {}
+
+// run_repl
+//
+// It reads lines with editing and history until Ctrl-D or `:quit`.
+// Incomplete input, e.g. an open block, continues on the next line with
+// the `..` prompt and Ctrl-C drops the input typed so far
+fn run_repl(repl: repl::Repl) {
+    let mut editor: Editor<Session, DefaultHistory> = Editor::new().unwrap_or_else(|error| {
+        eprintln!("Could not start the REPL: {}", error);
+        process::exit(1)
+    });
+    editor.set_helper(Some(Session { repl }));
+    let history = history_path();
+    if let Some(ref path) = history {
+        let _ = editor.load_history(path);
+    }
+
+    let mut source_code = String::new();
+    loop {
+        let prompt = if source_code.is_empty() { ">> " } else { ".. " };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                if !source_code.is_empty() {
+                    source_code.push('\n');
+                }
+                source_code.push_str(&line);
+                if repl::is_incomplete(&source_code) {
+                    continue
+                }
+
+                let _ = editor.add_history_entry(source_code.as_str());
+                let repl = &mut editor.helper_mut().expect("the session is the helper").repl;
+                match repl.run(source_code.clone()) {
+                    Ok(_) if repl.is_finished() => break,
+                    Err(Error::Runtime(RuntimeError::Exit { code, .. })) => {
+                        if let Some(ref path) = history {
+                            let _ = editor.save_history(path);
+                        }
+                        exit(code)
+                    },
+                    Ok(result) => println!("{}", result),
+                    Err(error) => eprint!("{}", repl.render_to_terminal(error, &source_code))
                 }
-                print!(">>");
-                io::stdout().flush().ok().expect("Ops... Something went wrong. :(");
+                source_code.clear();
+            },
+            Err(ReadlineError::Interrupted) => source_code.clear(),
+            Err(ReadlineError::Eof) => break,
+            Err(error) => {
+                eprintln!("{}", error);
+                break
             }
         }
     }
+
+    if let Some(ref path) = history {
+        let _ = editor.save_history(path);
+    }
 }
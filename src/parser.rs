@@ -1,5 +1,8 @@
 use token::{Token, Kind, Tokenizer};
+use error::SyntaxError;
 use ast;
+use stacker;
+use interpreter::{RED_ZONE, STACK_SEGMENT};
 
 // # Parser
 //
@@ -16,49 +19,149 @@ use ast;
 //
 //   statement: block
 //   statement: return_statement
+//   statement: BREAK
+//   statement: CONTINUE
 //   statement: while
+//   statement: for
 //   statement: if
 //   statement: define_statement
 //   statement: assign_statement
+//   statement: index_assign_statement
 //   statement: empty_statement
 //
 //   assign_statement: constant ASSIGN expr
+//   index_assign_statement: postfix ASSIGN expr
 //
 //   while: WHILE expr BEGIN statement_list END
 //
+//   for: FOR constant IN expr BEGIN statement_list END
+//
 //   if: IF expr BEGIN statement_list END
 //   if: IF expr BEGIN statement_list ELSE statement_lit END
 //
-//   define_statement: FUN variable ASSIGN ( params_list ) block
 //   define_statement: MUT variable
 //   define_statement: MUT variable ASSIGN expr
 //   define_statement: IMUT constant ASSIGN expr
 //
-//   expr:: term
-//   expr:: term (+|-) term
-//   expr:: term (OR) term
-//   expr:: term (==|!==|>|<) term
+//   expr:: unary
+//   expr:: expr BINARY_OPERATOR expr
+//
+//   unary:: (-|+|NOT) unary
+//   unary:: postfix
 //
-//   term:: factor
-//   term:: factor (*|/) factor
-//   term:: factor (AND) factor
+//   postfix:: factor
+//   postfix:: postfix [ expr ]
+//   postfix:: postfix DOT ID
 //
-//   factor:: (-|+) factor
 //   factor:: ( expr )
 //   factor:: INTEGER
 //   factor:: BOOLEAN
+//   factor:: STRING
 //   factor:: variable
 //   factor:: function_call
+//   factor:: function
+//   factor:: list
+//   factor:: map
+//
+//   map:: BEGIN END
+//   map:: BEGIN STRING COLON expr (SEPARATOR STRING COLON expr)* END
+//
+//   list:: [ ]
+//   list:: [ expr (SEPARATOR expr)* ]
+//
+//   function: FUN [ params_list ] block
+//   function: PIPE params_list PIPE expr
+//   function: OR expr
 //
 //   variable:: ID
+//
+//   params_list: [param]
+//   params_list: [param SEPARATOR params_list]
+//   params_list: [REST ID]
+//
+//   param: ID
+//   param: ID ASSIGN expr
 //```
+//
+// The binary operators of an `expr` are parsed by precedence climbing, see
+// `Precedence` for their binding power and associativity
 pub struct Parser {
     tokenizer: Tokenizer,
+    // How many statements and unary expressions are being parsed, one
+    // inside of the other
+    depth: usize
+}
+
+// The maximum nesting of statements and expressions. Parsing grows the
+// native stack as evaluating does, but the nodes are also compiled and
+// dropped recursively, so deeper sources result in a SyntaxError
+pub const MAX_NESTING: usize = 256;
+
+// # Precedence
+//
+// How tight the binary operators bind their operands, from the loosest to
+// the tightest. Every binary operator is left associative and the unary
+// operators (`-`, `+`, `not` and `!`) bind tighter than all of them
+//
+// | Precedence     | Operators             |
+// |----------------|-----------------------|
+// | Or             | `or` `||`             |
+// | And            | `and` `&&`            |
+// | Equality       | `==` `!=`             |
+// | Comparison     | `<` `>` `<=` `>=`     |
+// | Additive       | `+` `-`               |
+// | Multiplicative | `*` `/` `%`           |
+//
+// A new operator only needs a row here, in `Precedence::of`, and its
+// evaluation in the interpreter
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Precedence {
+    Lowest,
+    Or,
+    And,
+    Equality,
+    Comparison,
+    Additive,
+    Multiplicative
+}
+
+impl Precedence {
+    // of
+    //
+    // The precedence of a token used as a binary operator, if it is one
+    fn of(token: &Token) -> Option<Precedence> {
+        match (&token.kind, token.value.as_ref()) {
+            (&Kind::Comparison, "or") | (&Kind::Comparison, "||") => Some(Precedence::Or),
+            (&Kind::Comparison, "and") | (&Kind::Comparison, "&&") => Some(Precedence::And),
+            (&Kind::Comparison, "==") | (&Kind::Comparison, "!=") => Some(Precedence::Equality),
+            (&Kind::Comparison, _) => Some(Precedence::Comparison),
+            (&Kind::Operator, "+") | (&Kind::Operator, "-") => Some(Precedence::Additive),
+            (&Kind::Operator, _) => Some(Precedence::Multiplicative),
+            _ => None
+        }
+    }
 }
 
 impl Parser {
     pub fn new(lexer: Tokenizer) -> Self {
-        Parser { tokenizer: lexer }
+        Parser { tokenizer: lexer, depth: 0 }
+    }
+
+    // nested
+    //
+    // It parses one level deeper, up to MAX_NESTING
+    fn nested<F>(&mut self, parse: F) -> Result<ast::Node, SyntaxError>
+        where F: FnOnce(&mut Parser) -> Result<ast::Node, SyntaxError> {
+        if self.depth >= MAX_NESTING {
+            let span = self.tokenizer.advance().unexpected(vec![]).span;
+            let reason = format!("more than {} nested statements or expressions", MAX_NESTING);
+            let found = Token { kind: Kind::Invalid, value: reason, span };
+            return Err(SyntaxError::new(vec![], Some(found), span))
+        }
+        self.depth += 1;
+        let result = stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, || parse(self));
+        self.depth -= 1;
+        result
     }
 
     // function_call
@@ -69,12 +172,13 @@ impl Parser {
     //   function_call: ID BEGIN END
     //   function_call: ID BEGIN param_list END
     // ```
-    fn function_call(&mut self) -> ast::Node {
-        let name = self.variable();
-        self.tokenizer.advance().consume(Kind::GroupBegin);
-        let args = self.args_list();
-        self.tokenizer.advance().consume(Kind::GroupEnd);
-        ast::Node::call_function(name, args)
+    fn function_call(&mut self) -> Result<ast::Node, SyntaxError> {
+        let name = self.variable()?;
+        self.tokenizer.advance().consume(Kind::GroupBegin)?;
+        let args = self.args_list()?;
+        let end = self.tokenizer.advance().consume(Kind::GroupEnd)?;
+        let span = name.span.to(end.span);
+        Ok(ast::Node::call_function(name, args).at(span))
     }
 
 
@@ -85,11 +189,11 @@ impl Parser {
     // ```
     //   block: BEGIN statement_list END
     // ```
-    fn block(&mut self) -> ast::Node {
-        self.tokenizer.advance().consume(Kind::Begin);
-        let statement_list = self.statement_list();
-        self.tokenizer.advance().consume(Kind::End);
-        ast::Node::block(statement_list)
+    fn block(&mut self) -> Result<ast::Node, SyntaxError> {
+        let begin = self.tokenizer.advance().consume(Kind::Begin)?;
+        let statement_list = self.statement_list()?;
+        let end = self.tokenizer.advance().consume(Kind::End)?;
+        Ok(ast::Node::block(statement_list).at(begin.span.to(end.span)))
     }
 
     // statement_list
@@ -101,13 +205,13 @@ impl Parser {
     //   statement_list: statement
     //   statement_list: statement STATEMENT_END statement_list
     // ```
-    fn statement_list(&mut self) -> Vec<ast::Node> {
-        let mut statements = vec![self.statement()];
-        if let Some(Token{kind: Kind::StatementEnd, ..}) = self.tokenizer.advance().get() {
-            self.tokenizer.consume(Kind::StatementEnd);
-            statements.extend(self.statement_list())
+    fn statement_list(&mut self) -> Result<Vec<ast::Node>, SyntaxError> {
+        let mut statements = vec![self.nested(Parser::statement)?];
+        while let Some(Token{kind: Kind::StatementEnd, ..}) = self.tokenizer.advance().get() {
+            self.tokenizer.consume(Kind::StatementEnd)?;
+            statements.push(self.nested(Parser::statement)?)
         }
-        return statements
+        Ok(statements)
     }
 
     // statement
@@ -117,23 +221,36 @@ impl Parser {
     // ```
     //   statement: block
     //   statement: return_statement
+    //   statement: BREAK
+    //   statement: CONTINUE
     //   statement: while_block
+    //   statement: for_block
     //   statement: if_block
     //   statement: define_statement
     //   statement: assign_statement
     //   statement: std_output_statement
     //   statement: empty_statement
     // ```
-    fn statement(&mut self) -> ast::Node {
+    fn statement(&mut self) -> Result<ast::Node, SyntaxError> {
         let curr = self.tokenizer.advance().get();
         match curr {
             Some(Token{ kind: Kind::Return, ..}) => {
-                self.tokenizer.consume(Kind::Return);
-                ast::Node::_return(self.expr())
+                let keyword = self.tokenizer.consume(Kind::Return)?;
+                let expr = self.expr()?;
+                let span = keyword.span.to(expr.span);
+                Ok(ast::Node::_return(expr).at(span))
+            },
+            Some(Token{ kind: Kind::Break, ..}) => {
+                Ok(ast::Node::_break(self.tokenizer.consume(Kind::Break)?))
+            },
+            Some(Token{ kind: Kind::Continue, ..}) => {
+                Ok(ast::Node::_continue(self.tokenizer.consume(Kind::Continue)?))
             },
             Some(Token{ kind: Kind::StdOut, ..}) => {
-                self.tokenizer.consume(Kind::StdOut);
-                ast::Node::print(self.expr())
+                let keyword = self.tokenizer.consume(Kind::StdOut)?;
+                let expr = self.expr()?;
+                let span = keyword.span.to(expr.span);
+                Ok(ast::Node::print(expr).at(span))
             },
             Some(Token{ kind: Kind::ImmutableDefine, ..}) |
             Some(Token{ kind: Kind::MutableDefine, ..}) => {
@@ -142,18 +259,29 @@ impl Parser {
             Some(Token{ kind: Kind::ID, ..}) => {
                 let next = self.tokenizer.peek(0);
                 match next {
-                    Some(Token{kind: Kind::GroupBegin, ..}) =>
-                        self.function_call(),
-
                     Some(Token{kind: Kind::Assign, ..}) =>
                         self.assign_statement(),
 
+                    Some(Token{kind: Kind::FunctionParamBegin, ..}) |
+                    Some(Token{kind: Kind::Dot, ..}) =>
+                        self.index_assign_statement(),
+
                     _ => self.expr()
                 }
             },
-            Some(Token{ kind: Kind::Begin, ..}) => self.block(),
+            Some(Token{ kind: Kind::Begin, ..}) => {
+                // A map literal starts with a key followed by a colon, or is
+                // empty, any other braces are a block
+                match (self.tokenizer.peek(0), self.tokenizer.peek(1)) {
+                    (Some(Token{kind: Kind::Str, ..}), Some(Token{kind: Kind::Colon, ..})) |
+                    (Some(Token{kind: Kind::End, ..}), _) => self.expr(),
+                    _ => self.block()
+                }
+            },
             Some(Token{ kind: Kind::While, ..}) => self._while(),
+            Some(Token{ kind: Kind::For, ..}) => self._for(),
             Some(Token{ kind: Kind::If, ..}) => self._if(),
+            None => Ok(ast::Node::empty()),
             _ => self.expr()
         }
     }
@@ -165,12 +293,32 @@ impl Parser {
     // ```
     //   assign_statement: constant ASSIGN expr
     // ```
-    fn assign_statement(&mut self) -> ast::Node {
-        let (name, _, expr) = (self.variable(),
-            self.tokenizer.advance().consume(Kind::Assign).value,
-            self.expr()
-        );
-        ast::Node::reassign(name, expr)
+    fn assign_statement(&mut self) -> Result<ast::Node, SyntaxError> {
+        let name = self.variable()?;
+        self.tokenizer.advance().consume(Kind::Assign)?;
+        let expr = self.expr()?;
+        Ok(ast::Node::reassign(name, expr))
+    }
+
+    // index_assign_statement
+    //
+    // index_assign_statement is a variable followed by one or more indexes
+    // followed by an assign token followed by an expression (expr). Without
+    // the assign token it is just an expression.
+    // Represented as context free grammar:
+    // ```
+    //   index_assign_statement: postfix ASSIGN expr
+    // ```
+    fn index_assign_statement(&mut self) -> Result<ast::Node, SyntaxError> {
+        let target = self.expr()?;
+        if let Some(Token{kind: Kind::Assign, ..}) = self.tokenizer.advance().get() {
+            if let ast::Operation::Index(_, _) = *target.operation {
+                self.tokenizer.consume(Kind::Assign)?;
+                let expr = self.expr()?;
+                return Ok(ast::Node::index_assign(target, expr))
+            }
+        }
+        Ok(target)
     }
 
     // while
@@ -180,11 +328,31 @@ impl Parser {
     // ```
     //   while: WHILE expr BEGIN statement_list END
     // ```
-    fn _while(&mut self) -> ast::Node {
-        self.tokenizer.consume(Kind::While);
-        let conditional = self.expr();
-        let block = self.block();
-        ast::Node::conditional(conditional, block)
+    fn _while(&mut self) -> Result<ast::Node, SyntaxError> {
+        let keyword = self.tokenizer.consume(Kind::While)?;
+        let conditional = self.expr()?;
+        let block = self.block()?;
+        let span = keyword.span.to(block.span);
+        Ok(ast::Node::conditional(conditional, block).at(span))
+    }
+
+    // for
+    //
+    // for is a FOR followed by a variable followed by IN followed by the
+    // expression it iterates followed by a block
+    // Represented as context free grammar:
+    // ```
+    //   for: FOR constant IN expr BEGIN statement_list END
+    // ```
+    fn _for(&mut self) -> Result<ast::Node, SyntaxError> {
+        let keyword = self.tokenizer.consume(Kind::For)?;
+        self.tokenizer.advance();
+        let variable = self.constant()?;
+        self.tokenizer.advance().consume(Kind::In)?;
+        let iterable = self.expr()?;
+        let block = self.block()?;
+        let span = keyword.span.to(block.span);
+        Ok(ast::Node::for_in(variable, iterable, block).at(span))
     }
 
     // if
@@ -196,20 +364,21 @@ impl Parser {
     //   if: IF expr BEGIN statement_list END
     //   if: IF expr BEGIN statement_list ELSE statement_lit END
     // ```
-    fn _if(&mut self) -> ast::Node {
-        self.tokenizer.consume(Kind::If);
-        let condition = self.expr();
-        self.tokenizer.consume(Kind::Begin);
-        let if_node = ast::Node::block(self.statement_list());
+    fn _if(&mut self) -> Result<ast::Node, SyntaxError> {
+        let keyword = self.tokenizer.consume(Kind::If)?;
+        let condition = self.expr()?;
+        self.tokenizer.advance().consume(Kind::Begin)?;
+        let if_node = ast::Node::block(self.statement_list()?);
         let optional_elsenode =
             if let Some(Token{ kind: Kind::Else, ..}) = self.tokenizer.get() {
-                self.tokenizer.consume(Kind::Else);
-                ast::Node::block(self.statement_list())
+                self.tokenizer.consume(Kind::Else)?;
+                ast::Node::block(self.statement_list()?)
             } else {
                 ast::Node::empty()
             };
-        self.tokenizer.consume(Kind::End);
-        ast::Node::ifelse(condition, if_node, optional_elsenode)
+        let end = self.tokenizer.advance().consume(Kind::End)?;
+        Ok(ast::Node::ifelse(condition, if_node, optional_elsenode)
+           .at(keyword.span.to(end.span)))
     }
 
     // define_statement
@@ -218,50 +387,39 @@ impl Parser {
     // an assign token followed by an expression (expr).
     // Represented as context free grammar:
     // ```
-    //   define_statement: FUN variable ASSIGN ( params_list ) block
     //   define_statement: MUT variable
     //   define_statement: MUT variable ASSIGN expr
     //   define_statement: IMUT constant ASSIGN expr
     // ```
-    fn define_statement(&mut self) -> ast::Node {
+    fn define_statement(&mut self) -> Result<ast::Node, SyntaxError> {
         match self.tokenizer.get() {
             Some(Token{ kind: Kind::ImmutableDefine, ..}) => {
-                self.tokenizer.consume(Kind::ImmutableDefine);
+                let keyword = self.tokenizer.consume(Kind::ImmutableDefine)?;
                 self.tokenizer.advance();
-                let var = self.constant();
-                self.tokenizer.advance().consume(Kind::Assign);
-                println!("NEXT {:?}", self.tokenizer.advance().get());
-
-                match self.tokenizer.get() {
-                    Some(Token{ kind: Kind::FunctionDefine, ..}) => {
-                        self.tokenizer.consume(Kind::FunctionDefine);
-                        self.tokenizer.advance();
-
-                        self.tokenizer.advance().consume(Kind::FunctionParamBegin);
-                        let params = self.params_list();
-                        self.tokenizer.advance().consume(Kind::FunctionParamEnd);
-
-                        let block = self.block();
-
-                        ast::Node::define_function(var, params, block)
-                    },
-                    _ => {
-                        let expr = self.expr();
-                        ast::Node::define_immutable(var, expr)
-                    }
+                let var = self.constant()?;
+                self.tokenizer.advance().consume(Kind::Assign)?;
+
+                let expr = self.expr()?;
+                let span = keyword.span.to(expr.span);
+
+                // A function bound by let is defined by name, so it can
+                // call itself
+                match *expr.operation {
+                    ast::Operation::Function(params, block) =>
+                        Ok(ast::Node::define_function(var, params, block).at(span)),
+                    _ => Ok(ast::Node::define_immutable(var, expr).at(span))
                 }
             },
             Some(Token{ kind: Kind::MutableDefine, ..}) => {
-                self.tokenizer.consume(Kind::MutableDefine);
+                let keyword = self.tokenizer.consume(Kind::MutableDefine)?;
                 self.tokenizer.advance();
-                let (var, _, expr) = (
-                    self.constant(),
-                    self.tokenizer.advance().consume(Kind::Assign),
-                    self.expr()
-                 );
-                ast::Node::define_mutable(var, expr)
+                let var = self.constant()?;
+                self.tokenizer.advance().consume(Kind::Assign)?;
+                let expr = self.expr()?;
+                let span = keyword.span.to(expr.span);
+                Ok(ast::Node::define_mutable(var, expr).at(span))
             },
-            _ => ast::Node::empty()
+            _ => Ok(ast::Node::empty())
         }
     }
 
@@ -271,9 +429,9 @@ impl Parser {
     // ```
     //   constant: CONST
     // ```
-    fn constant(&mut self) -> ast::Node {
-        let token = self.tokenizer.consume(Kind::ID);
-        ast::Node::indentifier(Token::build(Kind::CONST, token.value))
+    fn constant(&mut self) -> Result<ast::Node, SyntaxError> {
+        let token = self.tokenizer.consume(Kind::ID)?;
+        Ok(ast::Node::indentifier(Token { kind: Kind::CONST, ..token }))
     }
 
     // variable
@@ -282,52 +440,119 @@ impl Parser {
     // ```
     //   variable: ID
     // ```
-    fn variable(&mut self) -> ast::Node {
-        let token = self.tokenizer.advance().consume(Kind::ID);
-        ast::Node::indentifier(token)
+    fn variable(&mut self) -> Result<ast::Node, SyntaxError> {
+        let token = self.tokenizer.advance().consume(Kind::ID)?;
+        Ok(ast::Node::indentifier(token))
     }
 
     // args_list
-    // args_list is an ID that can be followed by SEPARATOR followed by ID
+    // args_list is an expr that can be followed by SEPARATOR followed by
+    // expr, every separator comes after an argument
     // Represented as context free grammar:
     // ```
     //   args_list: expr
-    //   args_list: expr SEPARATOR expr]
+    //   args_list: expr SEPARATOR args_list
     // ```
-    fn args_list(&mut self) -> Vec<ast::Node> {
+    fn args_list(&mut self) -> Result<Vec<ast::Node>, SyntaxError> {
         let mut args = vec![];
-        match self.tokenizer.advance().get() {
-            Some(Token{kind: Kind::GroupEnd, ..}) => {
-                return args
-            },
-            Some(Token{kind: Kind::Separator, ..}) => {
-                self.tokenizer.consume(Kind::Separator);
-            },
-            _ => {
-                args.push(self.factor())
+        loop {
+            if let Some(Token{kind: Kind::GroupEnd, ..}) = self.tokenizer.advance().get() {
+                break
+            }
+            args.push(self.expr()?);
+            match self.tokenizer.advance().get() {
+                Some(Token{kind: Kind::Separator, ..}) => {
+                    self.tokenizer.consume(Kind::Separator)?;
+                },
+                _ => break
             }
         }
-        args.extend(self.args_list());
-        args
+        Ok(args)
     }
 
     // params_list
-    // params_list is an ID that can be followed by SEPARATOR followed by ID
+    // params_list is a param that can be followed by SEPARATOR followed by
+    // param. A param may have a default value and the last one may collect
+    // the remaining arguments
     // Represented as context free grammar:
     // ```
-    //   params_list: [ID]
-    //   params_list: [ID SEPARATOR params_list]
+    //   params_list: [param]
+    //   params_list: [param SEPARATOR params_list]
+    //   params_list: [REST ID]
+    //
+    //   param: ID
+    //   param: ID ASSIGN expr
     // ```
-    fn params_list(&mut self) -> Vec<ast::Node> {
+    fn params_list(&mut self) -> Result<Vec<ast::Param>, SyntaxError> {
         let mut params = vec![];
-        if let Some(Token{kind: Kind::ID, ..}) = self.tokenizer.advance().get() {
-            params.push(self.variable());
-            while let Some(Token{kind: Kind::Separator, ..}) = self.tokenizer.advance().get() {
-                self.tokenizer.consume(Kind::Separator);
-                params.push(self.variable())
+        loop {
+            match self.tokenizer.advance().get() {
+                Some(Token{kind: Kind::ID, ..}) => params.push(self.param()?),
+                Some(Token{kind: Kind::Rest, ..}) => {
+                    self.tokenizer.consume(Kind::Rest)?;
+                    let name = self.variable()?;
+                    params.push(ast::Param::rest(name.value));
+                    break
+                },
+                _ => break
+            }
+
+            match self.tokenizer.advance().get() {
+                Some(Token{kind: Kind::Separator, ..}) => {
+                    self.tokenizer.consume(Kind::Separator)?;
+                },
+                _ => break
+            }
+        }
+        Ok(params)
+    }
+
+    fn param(&mut self) -> Result<ast::Param, SyntaxError> {
+        let name = self.variable()?.value;
+        if let Some(Token{kind: Kind::Assign, ..}) = self.tokenizer.advance().get() {
+            self.tokenizer.consume(Kind::Assign)?;
+            return Ok(ast::Param::with_default(name, self.expr()?))
+        }
+        Ok(ast::Param::new(name))
+    }
+
+    // function
+    //
+    // function is a FUN followed by params_list between brackets followed by
+    // a block, or the short form: params_list between pipes followed by an
+    // expression. Represented as context free grammar:
+    // ```
+    //   function: FUN [ params_list ] block
+    //   function: PIPE params_list PIPE expr
+    //   function: OR expr
+    // ```
+    fn function(&mut self) -> Result<ast::Node, SyntaxError> {
+        match self.tokenizer.advance().get() {
+            Some(Token{ kind: Kind::FunctionDefine, ..}) => {
+                let keyword = self.tokenizer.consume(Kind::FunctionDefine)?;
+                self.tokenizer.advance().consume(Kind::FunctionParamBegin)?;
+                let params = self.params_list()?;
+                self.tokenizer.advance().consume(Kind::FunctionParamEnd)?;
+                let block = self.block()?;
+                let span = keyword.span.to(block.span);
+                Ok(ast::Node::function(params, block).at(span))
+            },
+            Some(Token{ kind: Kind::Pipe, ..}) => {
+                let pipe = self.tokenizer.consume(Kind::Pipe)?;
+                let params = self.params_list()?;
+                self.tokenizer.advance().consume(Kind::Pipe)?;
+                let body = self.expr()?;
+                let span = pipe.span.to(body.span);
+                Ok(ast::Node::function(params, body).at(span))
+            },
+            _ => {
+                // `||` is read as `or`, it begins a function without params
+                let pipes = self.tokenizer.consume(Kind::Comparison)?;
+                let body = self.expr()?;
+                let span = pipes.span.to(body.span);
+                Ok(ast::Node::function(vec![], body).at(span))
             }
         }
-        params
     }
 
     // factor
@@ -335,37 +560,45 @@ impl Parser {
     // factor can be a terminal Integer, result of a grouped expr,
     // unary result of a factor or a var. Represented as context free grammar:
     // ```
-    //  factor:: (-|+) factor
     //  factor:: INTEGER
+    //  factor:: FLOAT
     //  factor:: BOLEAN
+    //  factor:: STRING
     //  factor:: ( expr )
     //  factor:: variable
     //  factor:: constant
     //  factor:: function_call
+    //  factor:: function
+    //  factor:: list
+    //  factor:: map
     // ```
-    fn factor(&mut self) -> ast::Node {
+    fn factor(&mut self) -> Result<ast::Node, SyntaxError> {
         match self.tokenizer.advance().get() {
-            Some(Token{ kind: Kind::Operator, .. }) => {
-                ast::Node::unary(self.tokenizer.consume(Kind::Operator), self.factor())
-            },
-
             Some(Token{ kind: Kind::GroupBegin , .. }) => {
-                self.tokenizer.consume(Kind::GroupBegin);
-                let result = self.expr();
-                self.tokenizer.consume(Kind::GroupEnd);
-                result
+                self.tokenizer.consume(Kind::GroupBegin)?;
+                let result = self.expr()?;
+                self.tokenizer.advance().consume(Kind::GroupEnd)?;
+                Ok(result)
             },
 
             Some(Token{ kind: Kind::Integer, .. }) => {
-                ast::Node::constant(self.tokenizer.advance().consume(Kind::Integer))
+                Ok(ast::Node::constant(self.tokenizer.consume(Kind::Integer)?))
+            },
+
+            Some(Token{ kind: Kind::Float, .. }) => {
+                Ok(ast::Node::constant(self.tokenizer.consume(Kind::Float)?))
             },
 
             Some(Token{ kind: Kind::Bolean, .. }) => {
-                ast::Node::constant(self.tokenizer.advance().consume(Kind::Bolean))
+                Ok(ast::Node::constant(self.tokenizer.consume(Kind::Bolean)?))
+            },
+
+            Some(Token{ kind: Kind::Str, .. }) => {
+                Ok(ast::Node::constant(self.tokenizer.consume(Kind::Str)?))
             },
 
             Some(Token{ kind: Kind::ID, .. }) => {
-                if let Some(Token{kind: Kind::GroupBegin, ..}) = self.tokenizer.peek(1) {
+                if let Some(Token{kind: Kind::GroupBegin, ..}) = self.tokenizer.peek(0) {
                     self.function_call()
                 } else {
                     self.variable()
@@ -376,79 +609,187 @@ impl Parser {
                 self.constant()
             },
 
-            None => ast::Node::empty(),
+            Some(Token{ kind: Kind::FunctionDefine, .. }) |
+            Some(Token{ kind: Kind::Pipe, .. }) => self.function(),
+
+            Some(Token{ kind: Kind::FunctionParamBegin, .. }) => self.list(),
 
-            other =>
-            panic!("Factor error: exptected Operator|GroupBegin|Integer|ID
-                   found {:?}", other)
+            Some(Token{ kind: Kind::Begin, .. }) => self.map(),
+
+            Some(Token{ ref value, .. }) if value == "||" => self.function(),
+
+            _ => Err(self.tokenizer.unexpected(vec![
+                Kind::Operator, Kind::Not, Kind::GroupBegin, Kind::Integer, Kind::Float,
+                Kind::Bolean, Kind::Str, Kind::ID, Kind::FunctionDefine, Kind::Pipe,
+                Kind::FunctionParamBegin, Kind::Begin
+            ]))
+        }
+    }
+
+    // unary
+    //
+    // unary is a factor optionally preceded by prefix operators, which bind
+    // tighter than any binary operator. Represented as context free grammar:
+    // ```
+    //  unary:: (-|+|NOT) unary
+    //  unary:: postfix
+    // ```
+    fn unary(&mut self) -> Result<ast::Node, SyntaxError> {
+        match self.tokenizer.advance().get() {
+            Some(Token{ kind: Kind::Operator, ref value, .. }) if value == "-" || value == "+" => {
+                let operator = self.tokenizer.consume(Kind::Operator)?;
+                let operand = self.nested(Parser::unary)?;
+                Ok(ast::Node::unary(operator, operand))
+            },
+            Some(Token{ kind: Kind::Not, .. }) => {
+                let operator = self.tokenizer.consume(Kind::Not)?;
+                let operand = self.nested(Parser::unary)?;
+                Ok(ast::Node::unary(operator, operand))
+            },
+            _ => {
+                let factor = self.factor()?;
+                self.postfix(factor)
+            }
         }
     }
 
-    // term
+    // postfix
     //
-    // One term can be a `factor` or result of `factor * factor`
-    // or `factor / factor`. Represented in context free grammar:
-    // ```
-    // term:: factor
-    // term:: factor (*|/) factor
-    // term:: factor (!== | == | > | <) factor
-    // ```
-    fn term(&mut self) -> ast::Node {
-        let result = self.factor();
-
-        if let Some(token) = self.tokenizer.advance().get() {
-            match token.value.as_ref() {
-                "*" | "/" | "%" => {
-                    return ast::Node::binary(
-                        result.clone(),
-                        self.tokenizer.consume(Kind::Operator).value,
-                        self.factor())
+    // postfix is a factor followed by any number of indexes between
+    // brackets or keys after a dot, `m.key` being the same as `m["key"]`.
+    // Represented as context free grammar:
+    // ```
+    //  postfix:: factor
+    //  postfix:: postfix [ expr ]
+    //  postfix:: postfix DOT ID
+    // ```
+    fn postfix(&mut self, factor: ast::Node) -> Result<ast::Node, SyntaxError> {
+        let mut result = factor;
+        loop {
+            match self.tokenizer.advance().get() {
+                Some(Token{kind: Kind::FunctionParamBegin, ..}) => {
+                    self.tokenizer.consume(Kind::FunctionParamBegin)?;
+                    let index = self.expr()?;
+                    let end = self.tokenizer.advance().consume(Kind::FunctionParamEnd)?;
+                    let span = result.span.to(end.span);
+                    result = ast::Node::index(result, index).at(span);
                 },
-                "==" | "!=" | ">" | "<" => {
-                    return ast::Node::comparison(
-                        result.clone(),
-                        self.tokenizer.consume(Kind::Comparison).value,
-                        self.factor())
+                Some(Token{kind: Kind::Dot, ..}) => {
+                    self.tokenizer.consume(Kind::Dot)?;
+                    let key = self.tokenizer.advance().consume(Kind::ID)?;
+                    let span = result.span.to(key.span);
+                    let key = ast::Node::constant(Token { kind: Kind::Str, ..key });
+                    result = ast::Node::index(result, key).at(span);
                 },
-                _ => ()
-            };
+                _ => return Ok(result)
+            }
+        }
+    }
+
+    // map
+    //
+    // map is a sequence of entries separated by SEPARATOR between braces,
+    // each entry being a string key followed by COLON followed by an expr.
+    // Represented as context free grammar:
+    // ```
+    //  map:: BEGIN END
+    //  map:: BEGIN STRING COLON expr (SEPARATOR STRING COLON expr)* END
+    // ```
+    fn map(&mut self) -> Result<ast::Node, SyntaxError> {
+        let begin = self.tokenizer.consume(Kind::Begin)?;
+        let mut entries = vec![];
+        loop {
+            if let Some(Token{kind: Kind::End, ..}) = self.tokenizer.advance().get() {
+                break
+            }
+            let key = self.tokenizer.consume(Kind::Str)?;
+            self.tokenizer.advance().consume(Kind::Colon)?;
+            entries.push((key.value, self.expr()?));
+            match self.tokenizer.advance().get() {
+                Some(Token{kind: Kind::Separator, ..}) => {
+                    self.tokenizer.consume(Kind::Separator)?;
+                },
+                _ => break
+            }
         }
-        return result
+        let end = self.tokenizer.advance().consume(Kind::End)?;
+        Ok(ast::Node::map(entries).at(begin.span.to(end.span)))
+    }
+
+    // list
+    //
+    // list is a sequence of expr separated by SEPARATOR between brackets.
+    // Brackets after FUN are a params_list instead.
+    // Represented as context free grammar:
+    // ```
+    //  list:: [ ]
+    //  list:: [ expr (SEPARATOR expr)* ]
+    // ```
+    fn list(&mut self) -> Result<ast::Node, SyntaxError> {
+        let begin = self.tokenizer.consume(Kind::FunctionParamBegin)?;
+        let mut items = vec![];
+        loop {
+            if let Some(Token{kind: Kind::FunctionParamEnd, ..}) = self.tokenizer.advance().get() {
+                break
+            }
+            items.push(self.expr()?);
+            match self.tokenizer.advance().get() {
+                Some(Token{kind: Kind::Separator, ..}) => {
+                    self.tokenizer.consume(Kind::Separator)?;
+                },
+                _ => break
+            }
+        }
+        let end = self.tokenizer.advance().consume(Kind::FunctionParamEnd)?;
+        Ok(ast::Node::list(items).at(begin.span.to(end.span)))
     }
 
     // # expr
     //
-    // One expr can be a `term` or result of `term + term` or `term - term`
+    // One expr is an `unary` or binary operations between unary operands.
     // Represented in context free grammar:
     // ```
-    //   expr:: term
-    //   expr:: term (+|-) term
-    //   expr:: term (OR|AND) term
+    //   expr:: unary
+    //   expr:: expr BINARY_OPERATOR expr
     // ```
-    pub fn expr(&mut self) -> ast::Node {
-        let mut result = self.term();
+    pub fn expr(&mut self) -> Result<ast::Node, SyntaxError> {
+        self.binary(Precedence::Lowest)
+    }
+
+    // binary
+    //
+    // It parses the operations whose operators bind tighter than the given
+    // precedence. The right operand of an operator only takes operators
+    // tighter than itself, which makes every operator left associative:
+    // `2 - 3 - 4` is `(2 - 3) - 4`
+    fn binary(&mut self, min: Precedence) -> Result<ast::Node, SyntaxError> {
+        let mut left = self.nested(Parser::unary)?;
         while let Some(token) = self.tokenizer.advance().get() {
-            if token.kind == Kind::EOF { break }
-            match token.value.as_ref() {
-                "+" | "-" => {
-                    result = ast::Node::binary(result.clone(),
-                                            self.tokenizer.consume(Kind::Operator).value,
-                                            self.term())
-                },
-                "and"|"&&"|"or" | "||" => {
-                    result = ast::Node::comparison(
-                        result.clone(),
-                        self.tokenizer.consume(Kind::Comparison).value,
-                        self.term())
-                },
+            let precedence = match Precedence::of(&token) {
+                Some(precedence) if precedence > min => precedence,
                 _ => break
             };
+            let operator = self.tokenizer.consume(token.kind.clone())?;
+            let right = self.binary(precedence)?;
+
+            left = match operator.kind {
+                Kind::Operator => ast::Node::binary(left, operator.value, right),
+                _ => ast::Node::comparison(left, operator.value, right)
+            };
         }
-        result
+        Ok(left)
     }
 
-    pub fn parse(&mut self) -> ast::Node {
-        ast::Node::main(self.statement_list())
+    // parse
+    //
+    // It parses the whole source into a Main node, or results in the first
+    // SyntaxError found. Every token must be part of a statement
+    pub fn parse(&mut self) -> Result<ast::Node, SyntaxError> {
+        let statements = self.statement_list()?;
+        if self.tokenizer.advance().get().is_some() {
+            return Err(self.tokenizer.unexpected(vec![Kind::StatementEnd]))
+        }
+        Ok(ast::Node::main(statements))
     }
 }
 
@@ -470,7 +811,7 @@ fn it_parses_sum_as_node() {
                                      String::from("+"),
                                      String::from("1"));
 
-    assert_eq!(ast::Node::main(vec![expected]), parser.parse());
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
 }
 
 #[test]
@@ -487,7 +828,7 @@ fn it_parses_multiples_operation() {
     let rnode = ast::Node::constant(Token::build(Kind::Integer, String::from("4")));
 
     let expected = ast::Node::binary(firstsum, token.value, rnode);
-    assert_eq!(ast::Node::main(vec![expected]), parser.parse());
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
 }
 
 #[test]
@@ -504,7 +845,7 @@ fn it_parses_respecting_precedence() {
     let rnode = ast::Node::constant(Token::build(Kind::Integer, String::from("10")));
 
     let expected = ast::Node::binary(rnode, token.value, plusnode);
-    assert_eq!(ast::Node::main(vec![expected]), parser.parse());
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
 }
 
 #[test]
@@ -521,7 +862,7 @@ fn it_parses_respecting_parentesis_precedence() {
     let rnode = ast::Node::constant(Token::build(Kind::Integer, String::from("4")));
 
     let expected = ast::Node::binary(plusnode, token.value, rnode);
-    assert_eq!(ast::Node::main(vec![expected]), parser.parse());
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
 }
 
 #[test]
@@ -534,11 +875,11 @@ fn it_parses_simple_block() {
                                  String::from("+"),
                                  String::from("5"));
 
-    let var = ast::Node::indentifier(Token{ kind: Kind::ID, value: String::from("x")});
+    let var = ast::Node::indentifier(Token::build(Kind::ID, String::from("x")));
     let assign = ast::Node::define_mutable(var, expr);
 
     let expected = ast::Node::block(vec![assign]);
-    assert_eq!(ast::Node::main(vec![expected]), parser.parse());
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
 }
 
 #[test]
@@ -552,7 +893,7 @@ fn it_parses_block_single_expression() {
                                  String::from("5"));
 
     let expected = ast::Node::block(vec![expr]);
-    assert_eq!(ast::Node::main(vec![expected]), parser.parse());
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
 }
 
 #[test]
@@ -561,18 +902,18 @@ fn it_parses_multiple_statements() {
     let tokenizer = Tokenizer::new(String::from(text));
     let mut parser = Parser::new(tokenizer);
 
-    let yvar = ast::Node::indentifier(Token{ kind: Kind::CONST, value: String::from("y")});
-    let yvalue = ast::Node::constant(Token{ kind: Kind::Integer, value: String::from("100")});
+    let yvar = ast::Node::indentifier(Token::build(Kind::CONST, String::from("y")));
+    let yvalue = ast::Node::constant(Token::build(Kind::Integer, String::from("100")));
     let yassign = ast::Node::define_immutable(yvar, yvalue);
 
     let expr = test_node_builder(String::from("10"),
                                  String::from("+"),
                                  String::from("5"));
-    let xvar = ast::Node::indentifier(Token{ kind: Kind::ID, value: String::from("x")});
+    let xvar = ast::Node::indentifier(Token::build(Kind::ID, String::from("x")));
     let xassign = ast::Node::define_mutable(xvar, expr);
 
     let expected = ast::Node::main(vec![xassign, yassign]);
-    assert_eq!(expected, parser.parse());
+    assert_eq!(expected, parser.parse().unwrap());
 }
 
 #[test]
@@ -587,7 +928,7 @@ fn it_parses_bolean_comparison() {
     let statement = ast::Node::comparison(lcompar, tkcompar.value, rcompar);
 
     let expected = ast::Node::main(vec![statement]);
-    assert_eq!(expected, parser.parse());
+    assert_eq!(expected, parser.parse().unwrap());
 }
 
 #[test]
@@ -606,7 +947,7 @@ fn it_parses_bolean_expression() {
 
     let statement = ast::Node::comparison(rnode, token.value, comparison);
     let expected = ast::Node::main(vec![statement]);
-    assert_eq!(expected, parser.parse());
+    assert_eq!(expected, parser.parse().unwrap());
 }
 
 #[test]
@@ -628,7 +969,7 @@ fn it_parses_expressions_gt_lt() {
     let rnode = ast::Node::comparison(lcompar2, tkcompa2.value, rcompar2);
 
     let expected = ast::Node::comparison(lnode, token.value, rnode);
-    assert_eq!(ast::Node::main(vec![expected]), parser.parse());
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
 }
 
 
@@ -638,14 +979,15 @@ fn it_parses_function_define() {
     let tokenizer = Tokenizer::new(String::from(text));
     let mut parser = Parser::new(tokenizer);
 
-    let fun = ast::Node::indentifier(Token{ kind: Kind::ID, value: String::from("x")});
-    let arg = ast::Node::indentifier(Token{ kind: Kind::ID, value: String::from("arg")});
-    let arg2 = ast::Node::indentifier(Token{ kind: Kind::ID, value: String::from("arg2")});
-    let params = vec![arg, arg2];
+    let fun = ast::Node::indentifier(Token::build(Kind::ID, String::from("x")));
+    let params = vec![
+        ast::Param::new(String::from("arg")),
+        ast::Param::new(String::from("arg2"))
+    ];
 
-    let arg1 = ast::Node::indentifier(Token{ kind: Kind::ID, value: String::from("arg")});
+    let arg1 = ast::Node::indentifier(Token::build(Kind::ID, String::from("arg")));
     let plus = Token::build(Kind::Operator, String::from("+"));
-    let arg2 = ast::Node::indentifier(Token{ kind: Kind::ID, value: String::from("arg2")});
+    let arg2 = ast::Node::indentifier(Token::build(Kind::ID, String::from("arg2")));
     let expr = ast::Node::binary(arg1, plus.value, arg2);
     let nreturn = ast::Node::_return(expr);
 
@@ -653,6 +995,285 @@ fn it_parses_function_define() {
 
     let fundefine = ast::Node::define_function(fun, params, block);
     let program = ast::Node::block(vec![fundefine]);
-    assert_eq!(ast::Node::main(vec![program]), parser.parse());
+    assert_eq!(ast::Node::main(vec![program]), parser.parse().unwrap());
+}
+
+
+#[test]
+fn it_parses_default_and_rest_params() {
+    let text = "let x = fn [a, b = 1, ..rest] { a }";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let fun = ast::Node::indentifier(Token::build(Kind::ID, String::from("x")));
+    let default = ast::Node::constant(Token::build(Kind::Integer, String::from("1")));
+    let params = vec![
+        ast::Param::new(String::from("a")),
+        ast::Param::with_default(String::from("b"), default),
+        ast::Param::rest(String::from("rest"))
+    ];
+    let body = ast::Node::indentifier(Token::build(Kind::ID, String::from("a")));
+    let block = ast::Node::block(vec![body]);
+
+    let fundefine = ast::Node::define_function(fun, params, block);
+    assert_eq!(ast::Node::main(vec![fundefine]), parser.parse().unwrap());
+}
+
+#[test]
+fn it_results_in_syntax_error_for_params_after_rest() {
+    let text = "let x = fn [..rest, a] { a }";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let error = parser.parse().unwrap_err();
+    assert_eq!(vec![Kind::FunctionParamEnd], error.expected);
+}
+
+#[test]
+fn it_parses_expressions_as_args() {
+    let text = "foo(x + 1)";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let name = ast::Node::indentifier(Token::build(Kind::ID, String::from("foo")));
+    let x = ast::Node::indentifier(Token::build(Kind::ID, String::from("x")));
+    let one = ast::Node::constant(Token::build(Kind::Integer, String::from("1")));
+    let call = ast::Node::call_function(name, vec![ast::Node::binary(x, String::from("+"), one)]);
+
+    assert_eq!(ast::Node::main(vec![call]), parser.parse().unwrap());
+}
+
+#[test]
+fn it_parses_anonymous_functions_as_args() {
+    let text = "map(fn [x] { x }, |y| y)";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let name = ast::Node::indentifier(Token::build(Kind::ID, String::from("map")));
+    let x = ast::Node::indentifier(Token::build(Kind::ID, String::from("x")));
+    let fun = ast::Node::function(vec![ast::Param::new(String::from("x"))],
+                                  ast::Node::block(vec![x]));
+    let y = ast::Node::indentifier(Token::build(Kind::ID, String::from("y")));
+    let arrow = ast::Node::function(vec![ast::Param::new(String::from("y"))], y);
+    let call = ast::Node::call_function(name, vec![fun, arrow]);
+
+    assert_eq!(ast::Node::main(vec![call]), parser.parse().unwrap());
+}
+
+#[test]
+fn it_parses_break_and_continue() {
+    let text = "while true { break; continue }";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let condition = ast::Node::constant(Token::build(Kind::Bolean, String::from("true")));
+    let body = ast::Node::block(vec![
+        ast::Node::_break(Token::build(Kind::Break, String::from("break"))),
+        ast::Node::_continue(Token::build(Kind::Continue, String::from("continue")))
+    ]);
+    let expected = ast::Node::conditional(condition, body);
+
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
+}
+
+#[test]
+fn it_parses_operators_left_associative() {
+    let text = "2 * 3 * 4";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let product = test_node_builder(String::from("2"), String::from("*"), String::from("3"));
+    let four = ast::Node::constant(Token::build(Kind::Integer, String::from("4")));
+    let expected = ast::Node::binary(product, String::from("*"), four);
+
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
+}
+
+#[test]
+fn it_parses_chained_equality_left_associative() {
+    let text = "a == b == c";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let a = ast::Node::indentifier(Token::build(Kind::ID, String::from("a")));
+    let b = ast::Node::indentifier(Token::build(Kind::ID, String::from("b")));
+    let c = ast::Node::indentifier(Token::build(Kind::ID, String::from("c")));
+    let first = ast::Node::comparison(a, String::from("=="), b);
+    let expected = ast::Node::comparison(first, String::from("=="), c);
+
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
+}
+
+#[test]
+fn it_parses_arithmetic_tighter_than_comparisons() {
+    let text = "1 + 2 == 3 and not x < 1 or y";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let sum = test_node_builder(String::from("1"), String::from("+"), String::from("2"));
+    let three = ast::Node::constant(Token::build(Kind::Integer, String::from("3")));
+    let equality = ast::Node::comparison(sum, String::from("=="), three);
+
+    let x = ast::Node::indentifier(Token::build(Kind::ID, String::from("x")));
+    let not = ast::Node::unary(Token::build(Kind::Not, String::from("not")), x);
+    let one = ast::Node::constant(Token::build(Kind::Integer, String::from("1")));
+    let lesser = ast::Node::comparison(not, String::from("<"), one);
+
+    let and = ast::Node::comparison(equality, String::from("and"), lesser);
+    let y = ast::Node::indentifier(Token::build(Kind::ID, String::from("y")));
+    let expected = ast::Node::comparison(and, String::from("or"), y);
+
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
+}
+
+#[test]
+fn it_parses_list_literals_and_index_assign() {
+    let text = "xs[0] = [1, y]";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let xs = ast::Node::indentifier(Token::build(Kind::ID, String::from("xs")));
+    let zero = ast::Node::constant(Token::build(Kind::Integer, String::from("0")));
+    let target = ast::Node::index(xs, zero);
+    let one = ast::Node::constant(Token::build(Kind::Integer, String::from("1")));
+    let y = ast::Node::indentifier(Token::build(Kind::ID, String::from("y")));
+    let expected = ast::Node::index_assign(target, ast::Node::list(vec![one, y]));
+
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
+}
+
+#[test]
+fn it_parses_map_literals_and_dot_keys() {
+    let text = "m.a = { \"k\": 1 }";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let m = ast::Node::indentifier(Token::build(Kind::ID, String::from("m")));
+    let key = ast::Node::constant(Token::build(Kind::Str, String::from("a")));
+    let target = ast::Node::index(m, key);
+    let one = ast::Node::constant(Token::build(Kind::Integer, String::from("1")));
+    let map = ast::Node::map(vec![(String::from("k"), one)]);
+    let expected = ast::Node::index_assign(target, map);
+
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
+}
+
+#[test]
+fn it_parses_string_literal() {
+    let text = "\"foo\" + \"bar\"";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let lnode = ast::Node::constant(Token::build(Kind::Str, String::from("foo")));
+    let rnode = ast::Node::constant(Token::build(Kind::Str, String::from("bar")));
+    let expected = ast::Node::binary(lnode, String::from("+"), rnode);
+
+    assert_eq!(ast::Node::main(vec![expected]), parser.parse().unwrap());
+}
+
+#[test]
+fn it_results_in_syntax_error_for_unexpected_token() {
+    let text = "var x = 1;\nlet y = (x + 1;";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let error = parser.parse().unwrap_err();
+    assert_eq!(vec![Kind::GroupEnd], error.expected);
+    assert_eq!(Some(Token::build(Kind::StatementEnd, String::from(";"))), error.found);
+    assert_eq!((2, 15), (error.span.line, error.span.column));
+    assert_eq!(25, error.span.start);
+}
+
+#[test]
+fn it_results_in_syntax_error_for_unexpected_end_of_file() {
+    let text = "if 1 < 2 { 1 ";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let error = parser.parse().unwrap_err();
+    assert_eq!(vec![Kind::End], error.expected);
+    assert_eq!(None, error.found);
+    assert_eq!("Sintax error: expected End found end of file at line 1, column 14",
+               error.to_string());
 }
 
+#[test]
+fn it_results_in_syntax_error_for_tokens_left_after_the_statements() {
+    for text in ["1 2", "let x = 1 2", "[1][0] = 2", "\"a\"[0] = \"b\"", "f(1 2)"].iter() {
+        let mut parser = Parser::new(Tokenizer::new(text.to_string()));
+
+        let error = parser.parse().unwrap_err();
+        assert!(error.found.is_some(), "{}", text);
+    }
+}
+
+#[test]
+fn it_results_in_syntax_error_for_truncated_expressions() {
+    let mut parser = Parser::new(Tokenizer::new(String::from("1 +")));
+
+    let error = parser.parse().unwrap_err();
+    assert_eq!(None, error.found);
+    assert!(error.to_string().contains("found end of file"));
+    assert!(Parser::new(Tokenizer::new(String::from("let x = 1;"))).parse().is_ok());
+}
+
+#[test]
+fn it_results_in_syntax_error_for_too_deep_nesting() {
+    let nested = |depth: usize, open: &str, close: &str| {
+        format!("{}1{}", open.repeat(depth), close.repeat(depth))
+    };
+
+    for text in [nested(20000, "(", ")"), nested(20000, "[", "]"), nested(20000, "{", "}"),
+                 nested(20000, "-", "")].iter() {
+        let error = Parser::new(Tokenizer::new(text.clone())).parse().unwrap_err();
+        assert!(error.to_string().contains("more than 256 nested"), "{}", error);
+    }
+    assert!(Parser::new(Tokenizer::new(nested(100, "(", ")"))).parse().is_ok());
+    assert!(Parser::new(Tokenizer::new("1;".repeat(100000))).parse().is_ok());
+}
+
+#[test]
+fn it_results_in_syntax_error_for_args_missing_before_a_separator() {
+    for text in ["f(,)", "f(1,,2)", "f(,1)"].iter() {
+        let mut parser = Parser::new(Tokenizer::new(text.to_string()));
+
+        let error = parser.parse().unwrap_err();
+        assert_eq!(Some(Token::build(Kind::Separator, String::from(","))), error.found, "{}", text);
+    }
+    assert!(Parser::new(Tokenizer::new(String::from("f(1, 2,)"))).parse().is_ok());
+}
+
+#[test]
+fn it_results_in_syntax_error_for_invalid_string() {
+    let text = "let x = \"foo";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let error = parser.parse().unwrap_err();
+    assert_eq!("Sintax error: unterminated string literal at line 1, column 9",
+               error.to_string());
+}
+
+#[test]
+fn it_parses_nodes_with_source_spans() {
+    let text = "let x = 1;\nwhile x < 2 {\n  foo(x)\n}";
+    let tokenizer = Tokenizer::new(String::from(text));
+    let mut parser = Parser::new(tokenizer);
+
+    let tree = parser.parse().unwrap();
+    let statements = match *tree.operation {
+        ast::Operation::Main(statements) => statements,
+        _ => unreachable!()
+    };
+
+    assert_eq!((1, 1, 0, 9), (statements[0].span.line, statements[0].span.column,
+                              statements[0].span.start, statements[0].span.end));
+    assert_eq!((2, 1, 11, 35), (statements[1].span.line, statements[1].span.column,
+                                statements[1].span.start, statements[1].span.end));
+    if let ast::Operation::Loop(_, ref block) = *statements[1].operation {
+        if let ast::Operation::Block(ref calls) = *block.operation {
+            assert_eq!((3, 3, 27, 33), (calls[0].span.line, calls[0].span.column,
+                                        calls[0].span.start, calls[0].span.end));
+        }
+    }
+}
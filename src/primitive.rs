@@ -1,135 +1,747 @@
-use token::{Token, Kind};
-use ast::Node;
-use std::cmp::Ord;
+use token::{Token, Kind, Span};
+use ast::{Node, Param};
+use error::RuntimeError;
+use frame::Frame;
+use vm::Function;
+use diagnostics::suggest;
 use std::cmp::Ordering;
-use std::cmp::Eq;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::Hash;
+use std::rc::Rc;
 use std::ops::{Add, Sub, Mul, Div, Rem};
+#[cfg(feature = "bigint")]
+use num_bigint::BigInt;
+#[cfg(feature = "bigint")]
+use num_traits::{Signed, ToPrimitive};
 
-#[derive(Debug, Clone, PartialEq)]
+// Value
+//
+// The name embedders know a Type by: what rascal programs compute and
+// what Rust code passes to them
+pub type Value = Type;
+
+#[derive(Debug, Clone)]
 pub enum Type {
     Str(String),
-    Int(i32),
+    Int(i64),
+    // Integers beyond i64. Results that fit back in an i64 are always
+    // turned back into Int, so the two variants never hold the same value
+    #[cfg(feature = "bigint")]
+    BigInt(BigInt),
+    Float(f64),
     Bool(bool),
-    Func(Vec<Node>, Node),
+    Func(Rc<Closure>),
+    NativeFunc(Rc<NativeFunc>),
+    // A function compiled to bytecode, run by the Vm backend
+    Compiled(Rc<Function>),
+    List(Vec<Type>),
+    Map(BTreeMap<String, Type>),
     Nil
 }
 impl Type {
-    pub fn from(token: &Token) -> Type {
+    pub fn from_token(token: &Token) -> Type {
         match token.clone() {
-            Token{kind: Kind::Integer, value} => {
-                Type::Int(value.parse::<i32>().expect("Invalid integer value."))
+            Token{kind: Kind::Integer, value, ..} => integer(&value),
+            Token{kind: Kind::Float, value, ..} => {
+                Type::Float(value.parse::<f64>().expect("Invalid float value."))
             },
-            Token{kind: Kind::Bolean, value} =>
+            Token{kind: Kind::Bolean, value, ..} =>
                 Type::Bool(value=="true"),
+            Token{kind: Kind::Str, value, ..} =>
+                Type::Str(value),
             _ => Type::Nil
         }
     }
-    pub fn to_string(self) -> String {
-        match self {
-            Type::Func(_,_) => format!("function"),
-            Type::Str(s) => format!("{}", s),
-            Type::Int(s) => format!("{}", s),
-            Type::Bool(s) => format!("{}", s),
-            _ => String::new()
+    // inspect
+    //
+    // It represents the value as it would be written in the source, which
+    // differs from to_string only by quoting strings
+    pub fn inspect(&self) -> String {
+        match *self {
+            Type::Str(ref s) => format!("{:?}", s),
+            ref other => other.to_string()
+        }
+    }
+
+    // type_name
+    //
+    // The name of the type used when reporting errors
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            Type::Str(_) => "Str",
+            Type::Int(_) => "Int",
+            #[cfg(feature = "bigint")]
+            Type::BigInt(_) => "Int",
+            Type::Float(_) => "Float",
+            Type::Bool(_) => "Bool",
+            Type::Func(_) | Type::NativeFunc(_) | Type::Compiled(_) => "Func",
+            Type::List(_) => "List",
+            Type::Map(_) => "Map",
+            Type::Nil => "Nil"
+        }
+    }
+
+    // params
+    //
+    // The parameters of a function defined by a program, as they are written
+    pub fn params(&self) -> Option<Vec<String>> {
+        match *self {
+            Type::Func(ref closure) => Some(closure.params.iter().map(Param::to_string).collect()),
+            Type::Compiled(ref function) => Some(function.prototype.params.clone()),
+            _ => None
         }
     }
 
+    // as_bool
+    //
+    // Every value can be used as boolean: positive numbers, non empty
+    // strings, non empty collections and functions are true
     pub fn as_bool(&self) -> bool {
-        match self.clone() {
-            Type::Int(s) => (s > 0),
+        match *self {
+            Type::Int(s) => s > 0,
+            #[cfg(feature = "bigint")]
+            Type::BigInt(ref s) => s.is_positive(),
+            Type::Float(s) => s > 0.0,
             Type::Bool(s) => s,
-            Type::Nil => false,
-            _ => panic!("Value error: type {:?} cannot be used as boolean", self)
+            Type::Str(ref s) => !s.is_empty(),
+            Type::Func(_) | Type::NativeFunc(_) | Type::Compiled(_) => true,
+            Type::List(ref items) => !items.is_empty(),
+            Type::Map(ref entries) => !entries.is_empty(),
+            Type::Nil => false
+        }
+    }
+
+    // index
+    //
+    // It gets the item of a list, or the character of a string, at the
+    // given position, or the value of a map for the given key
+    pub fn index(&self, index: &Type) -> Result<Type, RuntimeError> {
+        match *self {
+            Type::Map(ref entries) => {
+                let key = key(index)?;
+                entries.get(key).cloned().ok_or_else(|| missing(key, entries))
+            },
+            Type::List(ref items) =>
+                Ok(items[position("List", index, items.len())?].clone()),
+            Type::Str(ref s) => {
+                let position = position("Str", index, s.chars().count())?;
+                Ok(Type::Str(s.chars().skip(position).take(1).collect()))
+            },
+            _ => Err(RuntimeError::InvalidOperand {
+                op: String::from("[]"),
+                operand: self.type_name().to_string(),
+                span: Span::default()
+            })
+        }
+    }
+
+    // set_index
+    //
+    // It replaces in place the item found following the given positions,
+    // e.g. [0, 1] replaces the second item of the first item of a list.
+    // The last key of a map path is added when missing
+    pub fn set_index(&mut self, path: &[Type], value: Type) -> Result<(), RuntimeError> {
+        let (index, rest) = match path.split_first() {
+            Some(split) => split,
+            None => {
+                *self = value;
+                return Ok(())
+            }
+        };
+        match *self {
+            Type::List(ref mut items) => {
+                let position = position("List", index, items.len())?;
+                items[position].set_index(rest, value)
+            },
+            Type::Map(ref mut entries) => {
+                let key = key(index)?;
+                if rest.is_empty() {
+                    entries.insert(key.to_string(), value);
+                    return Ok(())
+                }
+                match entries.get_mut(key) {
+                    Some(entry) => entry.set_index(rest, value),
+                    None => Err(RuntimeError::KeyNotFound {
+                        key: key.to_string(),
+                        suggestion: None,
+                        span: Span::default()
+                    })
+                }
+            },
+            _ => Err(RuntimeError::InvalidOperand {
+                op: String::from("[]="),
+                operand: self.type_name().to_string(),
+                span: Span::default()
+            })
+        }
+    }
+
+    // iterate
+    //
+    // The values a for loop goes through: the items of a list, the keys of
+    // a map or the characters of a string
+    pub fn iterate(self) -> Result<Vec<Type>, RuntimeError> {
+        match self {
+            Type::List(items) => Ok(items),
+            Type::Map(entries) => Ok(entries.into_keys().map(Type::Str).collect()),
+            Type::Str(s) => Ok(s.chars().map(|c| Type::Str(c.to_string())).collect()),
+            other => Err(RuntimeError::InvalidOperand {
+                op: String::from("for"),
+                operand: other.type_name().to_string(),
+                span: Span::default()
+            })
+        }
+    }
+
+    // compare
+    //
+    // It orders two values of the same type, or two numbers. NaN can't be
+    // ordered and other combinations result in a type mismatch for the
+    // given operator
+    pub fn compare(&self, other: &Type, op: &str) -> Result<Ordering, RuntimeError> {
+        self.partial_cmp(other).ok_or_else(|| {
+            if numbers(self, other).is_some() {
+                RuntimeError::Unordered { op: op.to_string(), span: Span::default() }
+            } else {
+                mismatch(op, self, other)
+            }
+        })
+    }
+}
+
+// A value as programs print it, strings without quotes. The items of lists
+// and maps are shown as in the source, see inspect
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Type::Func(_) | Type::NativeFunc(_) | Type::Compiled(_) => write!(f, "function"),
+            Type::Str(ref s) => write!(f, "{}", s),
+            Type::Int(s) => write!(f, "{}", s),
+            #[cfg(feature = "bigint")]
+            Type::BigInt(ref s) => write!(f, "{}", s),
+            Type::Float(s) => write!(f, "{:?}", s),
+            Type::Bool(s) => write!(f, "{}", s),
+            Type::List(ref items) => {
+                let items: Vec<String> = items.iter().map(Type::inspect).collect();
+                write!(f, "[{}]", items.join(", "))
+            },
+            Type::Map(ref entries) => {
+                let entries: Vec<String> = entries.iter()
+                    .map(|(key, value)| format!("{:?}: {}", key, value.inspect()))
+                    .collect();
+                write!(f, "{{{}}}", entries.join(", "))
+            },
+            Type::Nil => Ok(())
+        }
+    }
+}
+
+// Closure
+//
+// A function value: its parameters, its body and the frame where it was
+// defined, which its calls run inside of
+pub struct Closure {
+    pub params: Vec<Param>,
+    pub body: Node,
+    pub env: Rc<Frame>
+}
+
+impl Closure {
+    pub fn new(params: Vec<Param>, body: Node, env: Rc<Frame>) -> Self {
+        Closure { params, body, env }
+    }
+}
+
+// A closure is only equal to itself. Its environment may contain the
+// closure itself (recursion), so it is not compared nor printed
+impl PartialEq for Closure {
+    fn eq(&self, other: &Closure) -> bool {
+        ::std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Closure")
+            .field("params", &self.params)
+            .field("body", &self.body)
+            .finish()
+    }
+}
+
+// NativeFunc
+//
+// A function implemented in Rust, such as the builtins. It receives the
+// evaluated arguments, whose amount was already checked against its arity
+pub struct NativeFunc {
+    pub name: String,
+    pub min: usize,
+    pub max: Option<usize>,
+    pub func: Box<NativeFn>
+}
+
+pub type NativeFn = dyn Fn(&[Type]) -> Result<Type, RuntimeError>;
+
+impl NativeFunc {
+    pub fn new<F>(name: &str, min: usize, max: Option<usize>, func: F) -> Self
+        where F: Fn(&[Type]) -> Result<Type, RuntimeError> + 'static {
+        NativeFunc { name: name.to_string(), min, max, func: Box::new(func) }
+    }
+}
+
+impl PartialEq for NativeFunc {
+    fn eq(&self, other: &NativeFunc) -> bool {
+        ::std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for NativeFunc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NativeFunc")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+fn mismatch(op: &str, left: &Type, right: &Type) -> RuntimeError {
+    RuntimeError::TypeMismatch {
+        op: op.to_string(),
+        left: left.type_name().to_string(),
+        right: right.type_name().to_string(),
+        span: Span::default()
+    }
+}
+
+// key
+//
+// It validates an index of a map
+fn key(index: &Type) -> Result<&str, RuntimeError> {
+    match *index {
+        Type::Str(ref key) => Ok(key),
+        _ => Err(RuntimeError::TypeMismatch {
+            op: String::from("[]"),
+            left: String::from("Map"),
+            right: index.type_name().to_string(),
+            span: Span::default()
+        })
+    }
+}
+
+fn missing(key: &str, entries: &BTreeMap<String, Type>) -> RuntimeError {
+    RuntimeError::KeyNotFound {
+        key: key.to_string(),
+        suggestion: suggest(key, entries.keys()),
+        span: Span::default()
+    }
+}
+
+// position
+//
+// It validates an index for a collection of the given length
+fn position(collection: &str, index: &Type, length: usize) -> Result<usize, RuntimeError> {
+    match *index {
+        Type::Int(index) if index >= 0 && (index as usize) < length => Ok(index as usize),
+        Type::Int(index) => Err(RuntimeError::IndexOutOfBounds {
+            index,
+            length,
+            span: Span::default()
+        }),
+        _ => Err(RuntimeError::TypeMismatch {
+            op: String::from("[]"),
+            left: collection.to_string(),
+            right: index.type_name().to_string(),
+            span: Span::default()
+        })
+    }
+}
+
+// integer
+//
+// It reads an integer literal, which the Tokenizer only accepts when it
+// fits in an i64, unless integers have arbitrary precision
+#[cfg(not(feature = "bigint"))]
+fn integer(literal: &str) -> Type {
+    Type::Int(literal.parse().expect("Invalid integer value."))
+}
+
+#[cfg(feature = "bigint")]
+fn integer(literal: &str) -> Type {
+    big(literal.parse().expect("Invalid integer value."))
+}
+
+// checked
+//
+// It takes the result of a checked integer operation, which is None when
+// it overflows
+fn checked(op: &str, left: i64, right: i64, result: Option<i64>) -> Result<Type, RuntimeError> {
+    match result {
+        Some(value) => Ok(Type::Int(value)),
+        None => overflow(op, left, right)
+    }
+}
+
+#[cfg(not(feature = "bigint"))]
+fn overflow(op: &str, _: i64, _: i64) -> Result<Type, RuntimeError> {
+    Err(RuntimeError::Overflow { op: op.to_string(), span: Span::default() })
+}
+
+// overflow
+//
+// With bigint an operation that overflows is done again in arbitrary
+// precision
+#[cfg(feature = "bigint")]
+fn overflow(op: &str, left: i64, right: i64) -> Result<Type, RuntimeError> {
+    Ok(arbitrary(op, BigInt::from(left), BigInt::from(right)))
+}
+
+#[cfg(feature = "bigint")]
+fn arbitrary(op: &str, left: BigInt, right: BigInt) -> Type {
+    big(match op {
+        "+" => left + right,
+        "-" => left - right,
+        "*" => left * right,
+        "/" => left / right,
+        _ => left % right
+    })
+}
+
+// big
+//
+// It wraps an arbitrary precision integer, as an Int when it fits
+#[cfg(feature = "bigint")]
+fn big(value: BigInt) -> Type {
+    match value.to_i64() {
+        Some(value) => Type::Int(value),
+        None => Type::BigInt(value)
+    }
+}
+
+// bigints
+//
+// Operations with any arbitrary precision integer and another integer are
+// done in arbitrary precision
+#[cfg(feature = "bigint")]
+fn bigints(left: &Type, right: &Type) -> Option<(BigInt, BigInt)> {
+    match (left, right) {
+        (Type::BigInt(s), Type::BigInt(o)) => Some((s.clone(), o.clone())),
+        (Type::BigInt(s), Type::Int(o)) => Some((s.clone(), BigInt::from(*o))),
+        (Type::Int(s), Type::BigInt(o)) => Some((BigInt::from(*s), o.clone())),
+        _ => None
+    }
+}
+
+// numbers
+//
+// The promotion rule of mixed arithmetic: when any of the operands is a
+// Float both are taken as floats. Two Ints, or anything that isn't a
+// number, results in None
+fn numbers(left: &Type, right: &Type) -> Option<(f64, f64)> {
+    match (left, right) {
+        (Type::Float(s), Type::Float(o)) => Some((*s, *o)),
+        (Type::Int(s), Type::Float(o)) => Some((*s as f64, *o)),
+        (Type::Float(s), Type::Int(o)) => Some((*s, *o as f64)),
+        #[cfg(feature = "bigint")]
+        (Type::BigInt(s), Type::Float(o)) => s.to_f64().map(|s| (s, *o)),
+        #[cfg(feature = "bigint")]
+        (Type::Float(s), Type::BigInt(o)) => o.to_f64().map(|o| (*s, o)),
+        _ => None
+    }
+}
+
+// mixed
+//
+// It applies an arithmetic operation to operands that aren't both Int,
+// promoting them to Float with the given operation
+fn mixed<F>(op: &str, left: Type, right: Type, operation: F) -> Result<Type, RuntimeError>
+    where F: FnOnce(f64, f64) -> f64 {
+    #[cfg(feature = "bigint")]
+    {
+        if let Some((s, o)) = bigints(&left, &right) {
+            return Ok(arbitrary(op, s, o))
+        }
+    }
+    match numbers(&left, &right) {
+        Some((s, o)) => finite(op, operation(s, o)),
+        None => Err(mismatch(op, &left, &right))
+    }
+}
+
+// finite
+//
+// Floats don't go beyond the finite numbers: as integers overflowing, a
+// result that would be infinity or NaN is an error
+fn finite(op: &str, result: f64) -> Result<Type, RuntimeError> {
+    if result.is_finite() {
+        Ok(Type::Float(result))
+    } else {
+        Err(RuntimeError::NotFinite { op: op.to_string(), span: Span::default() })
+    }
+}
+
+fn is_number(value: &Type) -> bool {
+    match *value {
+        Type::Int(_) | Type::Float(_) => true,
+        #[cfg(feature = "bigint")]
+        Type::BigInt(_) => true,
+        _ => false
+    }
+}
+
+// is_zero
+//
+// Division by zero is an error for floats too, as any other operation
+// that would result in infinity or NaN, see finite
+fn is_zero(value: &Type) -> bool {
+    match *value {
+        Type::Int(value) => value == 0,
+        Type::Float(value) => value == 0.0,
+        _ => false
+    }
+}
+
+// Numbers are equal by value regardless of being Int or Float, so `1 == 1.0`
+// and NaN is not equal to anything, including itself
+impl PartialEq for Type {
+    fn eq(&self, other: &Type) -> bool {
+        if let Some((s, o)) = numbers(self, other) {
+            return s == o
+        }
+        match (self, other) {
+            (Type::Str(s), Type::Str(o)) => s == o,
+            (Type::Int(s), Type::Int(o)) => s == o,
+            #[cfg(feature = "bigint")]
+            (Type::BigInt(s), Type::BigInt(o)) => s == o,
+            (Type::Bool(s), Type::Bool(o)) => s == o,
+            (Type::Func(s), Type::Func(o)) => s == o,
+            (Type::NativeFunc(s), Type::NativeFunc(o)) => s == o,
+            (Type::Compiled(s), Type::Compiled(o)) => Rc::ptr_eq(s, o),
+            (Type::List(s), Type::List(o)) => s == o,
+            (Type::Map(s), Type::Map(o)) => s == o,
+            (Type::Nil, Type::Nil) => true,
+            _ => false
         }
     }
 }
 
 impl PartialOrd for Type {
     fn partial_cmp(&self, other: &Type) -> Option<Ordering> {
-        Some(self.cmp(other))
+        if let Some((s, o)) = numbers(self, other) {
+            return s.partial_cmp(&o)
+        }
+        #[cfg(feature = "bigint")]
+        {
+            if let Some((s, o)) = bigints(self, other) {
+                return s.partial_cmp(&o)
+            }
+        }
+        match (self, other) {
+            (Type::Bool(s), Type::Bool(o)) => s.partial_cmp(o),
+            (Type::Int(s), Type::Int(o)) => s.partial_cmp(o),
+            (Type::Str(s), Type::Str(o)) => s.partial_cmp(o),
+            (Type::List(s), Type::List(o)) => s.partial_cmp(o),
+            _ => None
+        }
+    }
+}
+
+// Conversions from Rust values, e.g. `Value::from(vec![1, 2])`
+impl From<i64> for Type {
+    fn from(value: i64) -> Type {
+        Type::Int(value)
     }
 }
 
-impl Ord for Type {
-    fn cmp(&self, other: &Type) -> Ordering {
-        match (self.clone(), other.clone()) {
-            (Type::Bool(s), Type::Bool(o)) => s.cmp(&o),
-            (Type::Int(s), Type::Int(o)) => s.cmp(&o),
-            _ => panic!("Operation error: invalid comparison {:?} {:?}",
-                        self,
-                        other)
+impl From<f64> for Type {
+    fn from(value: f64) -> Type {
+        Type::Float(value)
+    }
+}
+
+impl From<bool> for Type {
+    fn from(value: bool) -> Type {
+        Type::Bool(value)
+    }
+}
+
+impl From<String> for Type {
+    fn from(value: String) -> Type {
+        Type::Str(value)
+    }
+}
+
+impl<'a> From<&'a str> for Type {
+    fn from(value: &'a str) -> Type {
+        Type::Str(value.to_string())
+    }
+}
+
+impl<T: Into<Type>> From<Vec<T>> for Type {
+    fn from(items: Vec<T>) -> Type {
+        Type::List(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<K: Into<String>, T: Into<Type>, S> From<HashMap<K, T, S>> for Type {
+    fn from(entries: HashMap<K, T, S>) -> Type {
+        Type::Map(entries.into_iter().map(|(key, value)| (key.into(), value.into())).collect())
+    }
+}
+
+// Conversions to Rust values, e.g. `i64::try_from(value)?`. They result
+// in an error when the value is of another type
+fn conversion(expected: &str, value: &Type) -> RuntimeError {
+    RuntimeError::Conversion {
+        expected: expected.to_string(),
+        found: value.type_name().to_string(),
+        span: Span::default()
+    }
+}
+
+impl TryFrom<Type> for i64 {
+    type Error = RuntimeError;
+
+    fn try_from(value: Type) -> Result<i64, RuntimeError> {
+        match value {
+            Type::Int(value) => Ok(value),
+            other => Err(conversion("Int", &other))
+        }
+    }
+}
+
+impl TryFrom<Type> for f64 {
+    type Error = RuntimeError;
+
+    fn try_from(value: Type) -> Result<f64, RuntimeError> {
+        match value {
+            Type::Float(value) => Ok(value),
+            Type::Int(value) => Ok(value as f64),
+            other => Err(conversion("Float", &other))
+        }
+    }
+}
+
+impl TryFrom<Type> for bool {
+    type Error = RuntimeError;
+
+    fn try_from(value: Type) -> Result<bool, RuntimeError> {
+        match value {
+            Type::Bool(value) => Ok(value),
+            other => Err(conversion("Bool", &other))
+        }
+    }
+}
+
+impl TryFrom<Type> for String {
+    type Error = RuntimeError;
+
+    fn try_from(value: Type) -> Result<String, RuntimeError> {
+        match value {
+            Type::Str(value) => Ok(value),
+            other => Err(conversion("Str", &other))
+        }
+    }
+}
+
+impl<T: TryFrom<Type, Error = RuntimeError>> TryFrom<Type> for Vec<T> {
+    type Error = RuntimeError;
+
+    fn try_from(value: Type) -> Result<Vec<T>, RuntimeError> {
+        match value {
+            Type::List(items) => items.into_iter().map(T::try_from).collect(),
+            other => Err(conversion("List", &other))
         }
     }
 }
 
-impl Eq for Type {}
+impl<K, T> TryFrom<Type> for HashMap<K, T>
+    where K: From<String> + Eq + Hash, T: TryFrom<Type, Error = RuntimeError> {
+    type Error = RuntimeError;
+
+    fn try_from(value: Type) -> Result<HashMap<K, T>, RuntimeError> {
+        match value {
+            Type::Map(entries) => entries.into_iter()
+                .map(|(key, value)| Ok((K::from(key), T::try_from(value)?)))
+                .collect(),
+            other => Err(conversion("Map", &other))
+        }
+    }
+}
 
 impl Add for Type {
-    type Output = Type;
+    type Output = Result<Type, RuntimeError>;
 
-    fn add(self, other: Type) -> Type {
-        match (self.clone(), other.clone()) {
-            (Type::Int(s), Type::Int(o)) => Type::Int(s+o),
-            _ =>
-                panic!("Operation error: invalid add operation between
-                       {:?} and {:?}", self, other)
+    fn add(self, other: Type) -> Result<Type, RuntimeError> {
+        match (self, other) {
+            (Type::Int(s), Type::Int(o)) =>
+                checked("+", s, o, s.checked_add(o)),
+            (Type::Str(s), Type::Str(o)) => Ok(Type::Str(s + &o)),
+            (Type::List(mut s), Type::List(o)) => {
+                s.extend(o);
+                Ok(Type::List(s))
+            },
+            (s, o) => mixed("+", s, o, |s, o| s + o)
         }
     }
 }
 
 impl Sub for Type {
-    type Output = Type;
+    type Output = Result<Type, RuntimeError>;
 
-    fn sub(self, other: Type) -> Type {
-        match (self.clone(), other.clone()) {
-            (Type::Int(s), Type::Int(o)) => Type::Int(s-o),
-            _ =>
-                panic!("Operation error: invalid add operation between
-                       {:?} and {:?}", self, other)
+    fn sub(self, other: Type) -> Result<Type, RuntimeError> {
+        match (self, other) {
+            (Type::Int(s), Type::Int(o)) =>
+                checked("-", s, o, s.checked_sub(o)),
+            (s, o) => mixed("-", s, o, |s, o| s - o)
         }
     }
 }
 
 impl Mul for Type {
-    type Output = Type;
+    type Output = Result<Type, RuntimeError>;
 
-    fn mul(self, other: Type) -> Type {
-        match (self.clone(), other.clone()) {
-            (Type::Int(s), Type::Int(o)) => Type::Int(s*o),
-            _ =>
-                panic!("Operation error: invalid add operation between
-                       {:?} and {:?}", self, other)
+    fn mul(self, other: Type) -> Result<Type, RuntimeError> {
+        match (self, other) {
+            (Type::Int(s), Type::Int(o)) =>
+                checked("*", s, o, s.checked_mul(o)),
+            (s, o) => mixed("*", s, o, |s, o| s * o)
         }
     }
 }
 
 impl Div for Type {
-    type Output = Type;
+    type Output = Result<Type, RuntimeError>;
 
-    fn div(self, other: Type) -> Type {
-        match (self.clone(), other.clone()) {
-            (Type::Int(s), Type::Int(o)) => Type::Int(s/o),
-            _ =>
-                panic!("Operation error: invalid add operation between
-                       {:?} and {:?}", self, other)
+    fn div(self, other: Type) -> Result<Type, RuntimeError> {
+        if is_number(&self) && is_zero(&other) {
+            return Err(RuntimeError::DivisionByZero { span: Span::default() })
+        }
+        match (self, other) {
+            (Type::Int(s), Type::Int(o)) =>
+                checked("/", s, o, s.checked_div(o)),
+            (s, o) => mixed("/", s, o, |s, o| s / o)
         }
     }
 }
 
 impl Rem for Type {
-    type Output = Type;
+    type Output = Result<Type, RuntimeError>;
 
-    fn rem(self, other: Type) -> Type {
-        match (self.clone(), other.clone()) {
-            (Type::Int(s), Type::Int(o)) => Type::Int(s%o),
-            _ =>
-                panic!("Operation error: invalid add operation between
-                       {:?} and {:?}", self, other)
+    fn rem(self, other: Type) -> Result<Type, RuntimeError> {
+        if is_number(&self) && is_zero(&other) {
+            return Err(RuntimeError::DivisionByZero { span: Span::default() })
+        }
+        match (self, other) {
+            (Type::Int(s), Type::Int(o)) =>
+                checked("%", s, o, s.checked_rem(o)),
+            (s, o) => mixed("%", s, o, |s, o| s % o)
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::nonminimal_bool, clippy::overly_complex_bool_expr)]
 mod integer {
     use primitive::Type;
 
@@ -155,6 +767,70 @@ mod integer {
 }
 
 #[cfg(test)]
+mod float {
+    use primitive::Type;
+    use error::RuntimeError;
+    use token::Span;
+    use std::f64;
+
+    #[test]
+    fn it_promotes_mixed_operands() {
+        assert_eq!(Ok(Type::Float(2.5)), Type::Int(2) + Type::Float(0.5));
+        assert_eq!(Ok(Type::Float(1.5)), Type::Float(3.0) / Type::Int(2));
+        assert_eq!(Ok(Type::Int(1)), Type::Int(3) / Type::Int(2));
+        assert_eq!(Ok(Type::Float(0.5)), Type::Float(2.5) % Type::Int(2));
+    }
+
+    #[test]
+    fn it_compares_with_integers() {
+        assert!(Type::Int(1) == Type::Float(1.0));
+        assert!(Type::Int(1) < Type::Float(1.5));
+        assert!(Type::List(vec![Type::Float(2.0)]) == Type::List(vec![Type::Int(2)]));
+    }
+
+    #[test]
+    fn it_results_in_error_comparing_nan() {
+        let nan = Type::Float(f64::NAN);
+        assert!(nan != nan);
+        assert_eq!(Err(RuntimeError::Unordered { op: String::from("<"), span: Span::default() }),
+                   nan.compare(&Type::Int(1), "<"));
+    }
+
+    // Floats are always finite numbers: the operations that would result
+    // in infinity or NaN by IEEE 754 are errors instead
+
+    #[test]
+    fn it_results_in_division_by_zero() {
+        assert_eq!(Err(RuntimeError::DivisionByZero { span: Span::default() }),
+                   Type::Float(1.0) / Type::Float(0.0));
+        assert_eq!(Err(RuntimeError::DivisionByZero { span: Span::default() }),
+                   Type::Float(0.0) / Type::Float(0.0));
+        assert_eq!(Err(RuntimeError::DivisionByZero { span: Span::default() }),
+                   Type::Int(1) % Type::Float(0.0));
+    }
+
+    #[test]
+    fn it_results_in_error_beyond_the_finite_numbers() {
+        assert_eq!(Err(RuntimeError::NotFinite { op: String::from("*"), span: Span::default() }),
+                   Type::Float(1e300) * Type::Float(1e300));
+        assert_eq!(Err(RuntimeError::NotFinite { op: String::from("-"), span: Span::default() }),
+                   Type::Float(-f64::MAX) - Type::Float(f64::MAX));
+        assert_eq!(Err(RuntimeError::NotFinite { op: String::from("+"), span: Span::default() }),
+                   Type::Float(f64::NAN) + Type::Int(1));
+        assert_eq!(Ok(Type::Float(f64::MAX)), Type::Float(f64::MAX) + Type::Float(1.0));
+    }
+
+    #[test]
+    fn it_formats_to_be_read_back() {
+        assert_eq!("1.0", Type::Float(1.0).to_string());
+        assert_eq!("0.001", Type::Float(1e-3).to_string());
+        assert_eq!("1e21", Type::Float(1e21).to_string());
+        assert_eq!("0.1", Type::Float(0.1).to_string());
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::nonminimal_bool, clippy::overly_complex_bool_expr)]
 mod boolean {
     use primitive::Type;
 
@@ -174,3 +850,140 @@ mod boolean {
         assert_eq!(false, false || Type::Bool(false).as_bool());
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod string {
+    use primitive::Type;
+
+    #[test]
+    fn it_concatenates() {
+        assert_eq!(Ok(Type::Str(String::from("foobar"))),
+                   Type::Str(String::from("foo")) + Type::Str(String::from("bar")));
+    }
+
+    #[test]
+    fn it_compares() {
+        assert_eq!(true, Type::Str(String::from("a")) == Type::Str(String::from("a")));
+        assert_eq!(true, Type::Str(String::from("a")) != Type::Str(String::from("b")));
+        assert_eq!(true, Type::Str(String::from("a")) < Type::Str(String::from("b")));
+        assert_eq!(false, Type::Str(String::from("a")) > Type::Str(String::from("b")));
+    }
+}
+
+#[cfg(test)]
+mod operations {
+    use primitive::Type;
+    use error::RuntimeError;
+    use token::Span;
+
+    #[test]
+    fn it_results_in_type_mismatch() {
+        assert_eq!(
+            Err(RuntimeError::TypeMismatch {
+                op: String::from("+"),
+                left: String::from("Int"),
+                right: String::from("Str"),
+                span: Span::default()
+            }),
+            Type::Int(1) + Type::Str(String::from("a")));
+        assert!(Type::Int(1).compare(&Type::Bool(true), "<").is_err());
+    }
+
+    #[test]
+    fn it_results_in_division_by_zero() {
+        assert_eq!(Err(RuntimeError::DivisionByZero { span: Span::default() }),
+                   Type::Int(1) / Type::Int(0));
+        assert_eq!(Err(RuntimeError::DivisionByZero { span: Span::default() }),
+                   Type::Int(1) % Type::Int(0));
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn it_results_in_overflow() {
+        assert_eq!(Err(RuntimeError::Overflow { op: String::from("*"), span: Span::default() }),
+                   Type::Int(i64::MAX) * Type::Int(2));
+    }
+}
+
+#[cfg(test)]
+mod list {
+    use primitive::Type;
+    use error::RuntimeError;
+    use token::Span;
+
+    fn list(items: Vec<i64>) -> Type {
+        Type::List(items.into_iter().map(Type::Int).collect())
+    }
+
+    #[test]
+    fn it_concatenates() {
+        assert_eq!(Ok(list(vec![1, 2, 3])), list(vec![1]) + list(vec![2, 3]));
+    }
+
+    #[test]
+    fn it_indexes() {
+        assert_eq!(Ok(Type::Int(2)), list(vec![1, 2]).index(&Type::Int(1)));
+        assert_eq!(Ok(Type::Str(String::from("ü"))),
+                   Type::Str(String::from("aü")).index(&Type::Int(1)));
+    }
+
+    #[test]
+    fn it_results_in_out_of_bounds() {
+        let error = RuntimeError::IndexOutOfBounds { index: 2, length: 2, span: Span::default() };
+        assert_eq!(Err(error), list(vec![1, 2]).index(&Type::Int(2)));
+    }
+
+    #[test]
+    fn it_sets_nested_items_in_place() {
+        let mut nested = Type::List(vec![list(vec![1, 2]), list(vec![3])]);
+        nested.set_index(&[Type::Int(0), Type::Int(1)], Type::Int(5)).unwrap();
+
+        assert_eq!(Type::List(vec![list(vec![1, 5]), list(vec![3])]), nested);
+    }
+}
+
+#[cfg(test)]
+mod map {
+    use primitive::Type;
+    use error::RuntimeError;
+    use token::Span;
+    use std::collections::BTreeMap;
+
+    fn map(entries: Vec<(&str, i64)>) -> Type {
+        Type::Map(entries.into_iter()
+                  .map(|(key, value)| (key.to_string(), Type::Int(value)))
+                  .collect::<BTreeMap<_, _>>())
+    }
+
+    #[test]
+    fn it_compares_structurally() {
+        assert_eq!(map(vec![("a", 1), ("b", 2)]), map(vec![("b", 2), ("a", 1)]));
+        assert!(map(vec![("a", 1)]) != map(vec![("a", 2)]));
+    }
+
+    #[test]
+    fn it_gets_values_by_key() {
+        let key = Type::Str(String::from("b"));
+        assert_eq!(Ok(Type::Int(2)), map(vec![("a", 1), ("b", 2)]).index(&key));
+    }
+
+    #[test]
+    fn it_results_in_key_not_found_with_suggestion() {
+        let key = Type::Str(String::from("nme"));
+        let error = RuntimeError::KeyNotFound {
+            key: String::from("nme"),
+            suggestion: Some(String::from("name")),
+            span: Span::default()
+        };
+        assert_eq!(Err(error), map(vec![("name", 1)]).index(&key));
+    }
+
+    #[test]
+    fn it_inserts_missing_keys() {
+        let mut entries = map(vec![("a", 1)]);
+        entries.set_index(&[Type::Str(String::from("b"))], Type::Int(2)).unwrap();
+
+        assert_eq!(map(vec![("a", 1), ("b", 2)]), entries);
+    }
+}
@@ -1,18 +1,316 @@
-use token::Tokenizer;
+use std::fs::File;
+use std::io::prelude::*;
+
+use interpreter::{Interpreter, Backend};
+use diagnostics::Diagnostic;
+use error::Error;
 use parser::Parser;
-use interpreter::Interpreter;
+use token::{Kind, Tokenizer};
+
+const COMMANDS: &str = "\
+:env           lists the variables defined in the session
+:type <expr>   shows the type of the expression value
+:ast <expr>    shows the tree parsed from the expression
+:tokens <expr> shows the tokens read from the expression
+:load <file>   runs the file in the session
+:reset         starts a new session
+:quit          leaves the REPL";
 
 pub struct Repl{
-    interpreter: Interpreter
+    interpreter: Interpreter,
+    backend: Backend,
+    finished: bool,
+    // The file and source run by the last `:load`, where its errors are
+    loaded: Option<(String, String)>
+}
+impl Default for Repl {
+    fn default() -> Self {
+        Repl::new()
+    }
 }
+
 impl Repl {
     pub fn new() -> Self {
-        Repl { interpreter: Interpreter::new() }
+        Repl::with_backend(Backend::default())
+    }
+
+    pub fn with_backend(backend: Backend) -> Self {
+        Repl::with_interpreter(Interpreter::with_backend(backend))
     }
 
     pub fn eval(&mut self, source: String) -> String {
-        let tokenizer = Tokenizer::new(source);
-        let mut parser = Parser::new(tokenizer);
-        self.interpreter.eval(parser.parse())
+        match self.run(source) {
+            Ok(result) => result,
+            Err(error) => error.to_string()
+        }
+    }
+
+    // with_interpreter
+    //
+    // A session that goes on with the definitions of the given interpreter
+    pub fn with_interpreter(interpreter: Interpreter) -> Self {
+        let backend = interpreter.backend();
+        Repl { interpreter, backend, finished: false, loaded: None }
+    }
+
+    // is_finished
+    //
+    // Whether the session was left with `:quit`
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // run
+    //
+    // It interprets the source code inside of the session, resulting in
+    // the Error that stopped it. Lines starting with `:` are commands that
+    // show the session internals instead
+    pub fn run(&mut self, source: String) -> Result<String, Error> {
+        self.loaded = None;
+        if source.trim_start().starts_with(':') {
+            return self.command(source.trim())
+        }
+        Ok(self.interpreter.eval(&source)?.to_string())
+    }
+
+    // render_to_terminal
+    //
+    // The diagnostic of an error of the given input to be printed on
+    // stderr. The errors of a file run by `:load` point to the file instead
+    pub fn render_to_terminal(&self, error: Error, input: &str) -> String {
+        match self.loaded {
+            Some((ref file, ref source)) => Diagnostic::from(error).render_to_terminal(file, source),
+            None => Diagnostic::from(error).render_to_terminal("<repl>", input)
+        }
+    }
+
+    // complete
+    //
+    // The completions of the word before the given position of the line,
+    // from the keywords and the visible names, and where the word starts.
+    // A function that is the only completion is completed up to its `(`
+    pub fn complete(&self, line: &str, position: usize) -> (usize, Vec<String>) {
+        let start = word_start(&line[..position]);
+        let word = &line[start..position];
+
+        let mut candidates: Vec<String> = Kind::keywords().into_iter()
+            .map(String::from)
+            .chain(self.interpreter.names())
+            .filter(|name| name.starts_with(word))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+
+        if candidates.len() == 1 && self.params(&candidates[0]).is_some() {
+            candidates[0].push('(');
+        }
+        (start, candidates)
+    }
+
+    // hint
+    //
+    // The parameters of the function being called right before the given
+    // position of the line, e.g. `x, y)` for `add(` after `let add = fn [x, y] {..}`
+    pub fn hint(&self, line: &str, position: usize) -> Option<String> {
+        let call = line[..position].strip_suffix('(')?;
+        let start = word_start(call);
+        self.params(&call[start..]).map(|params| format!("{})", params.join(", ")))
+    }
+
+    fn params(&self, name: &str) -> Option<Vec<String>> {
+        self.interpreter.get_global(name).and_then(|value| value.params())
+    }
+
+    fn command(&mut self, line: &str) -> Result<String, Error> {
+        let (command, argument) = match line.find(' ') {
+            Some(at) => (&line[..at], line[at..].trim()),
+            None => (line, "")
+        };
+
+        match command {
+            ":env" => {
+                let bindings: Vec<String> = self.interpreter.bindings().into_iter()
+                    .map(|(name, value, mutable)| {
+                        let keyword = if mutable { "var" } else { "let" };
+                        format!("{} {} = {}", keyword, name, value.inspect())
+                    })
+                    .collect();
+                Ok(bindings.join("\n"))
+            },
+
+            ":type" => Ok(self.interpreter.eval(argument)?.type_name().to_string()),
+
+            ":ast" => {
+                let tree = Parser::new(Tokenizer::new(argument.to_string())).parse()?;
+                Ok(tree.pretty())
+            },
+
+            ":tokens" => {
+                let tokens: Vec<String> = Tokenizer::new(argument.to_string())
+                    .map(|token| format!("{:?} {:?} at {}:{}",
+                                         token.kind, token.value, token.span.line, token.span.column))
+                    .collect();
+                Ok(tokens.join("\n"))
+            },
+
+            ":load" => {
+                let mut source = String::new();
+                if let Err(error) = File::open(argument).and_then(|mut file| file.read_to_string(&mut source)) {
+                    return Ok(format!("Can't read {}: {}", argument, error))
+                }
+                let result = self.interpreter.eval(&source);
+                self.loaded = Some((argument.to_string(), source));
+                Ok(result?.to_string())
+            },
+
+            ":reset" => {
+                let process = self.interpreter.allows_process_access();
+                self.interpreter = Interpreter::with_backend(self.backend);
+                if process {
+                    self.interpreter.allow_process_access();
+                }
+                Ok(String::new())
+            },
+
+            ":quit" => {
+                self.finished = true;
+                Ok(String::new())
+            },
+
+            _ => Ok(format!("Unknown command {}, the commands are:\n{}", command, COMMANDS))
+        }
+    }
+}
+
+// word_start
+//
+// Where the identifier at the end of the text starts
+fn word_start(text: &str) -> usize {
+    text.char_indices().rev()
+        .find(|&(_, c)| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |(at, c)| at + c.len_utf8())
+}
+
+// is_incomplete
+//
+// Whether the source code needs more lines to be a whole program: it has
+// unclosed blocks, parentheses, brackets or strings, or it ends with an
+// operator. Extra closing ones are left for the parser to report
+pub fn is_incomplete(source: &str) -> bool {
+    let mut depth = 0;
+    let mut last = None;
+    for token in Tokenizer::new(source.to_string()) {
+        match token.kind {
+            Kind::Begin | Kind::GroupBegin | Kind::FunctionParamBegin => depth += 1,
+            Kind::End | Kind::GroupEnd | Kind::FunctionParamEnd => depth -= 1,
+            Kind::Invalid if token.value == "unterminated string literal" => return true,
+            _ => ()
+        }
+        last = Some(token.kind);
+    }
+
+    match last {
+        Some(Kind::Operator) | Some(Kind::Comparison) | Some(Kind::Not) |
+        Some(Kind::Assign) => true,
+        _ => depth > 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+    use repl::{Repl, is_incomplete};
+    use interpreter::{Interpreter, Backend};
+
+    #[test]
+    fn it_lists_the_session_variables() {
+        let mut repl = Repl::with_backend(Backend::Tree);
+        repl.eval(String::from("let x = 1; var name = \"rascal\""));
+
+        assert_eq!("var name = \"rascal\"\nlet x = 1", repl.eval(String::from(":env")));
+    }
+
+    #[test]
+    fn it_shows_types_trees_and_tokens() {
+        let mut repl = Repl::with_backend(Backend::Tree);
+
+        assert_eq!("Float", repl.eval(String::from(":type 1 + 0.5")));
+        assert_eq!("Main\n  Binary +\n    Constant 1\n    Identifier x",
+                   repl.eval(String::from(":ast 1 + x")));
+        assert_eq!("Integer \"1\" at 1:1\nOperator \"+\" at 1:3",
+                   repl.eval(String::from(":tokens 1 +")));
+    }
+
+    #[test]
+    fn it_completes_keywords_and_names() {
+        let mut repl = Repl::with_backend(Backend::Tree);
+        repl.eval(String::from("let total = 1; let to_list = fn [x, ..rest] { [x] + rest }"));
+
+        assert_eq!((0, vec![String::from("to_list"), String::from("total")]), repl.complete("to", 2));
+        assert_eq!((4, vec![String::from("while")]), repl.complete("1 + whi", 7));
+        assert_eq!((6, vec![String::from("abs")]), repl.complete("print(ab", 8));
+        assert_eq!((0, vec![String::from("to_list(")]), repl.complete("to_", 3));
+        assert_eq!(Some(String::from("x, ..rest)")), repl.hint("to_list(", 8));
+        assert_eq!(None, repl.hint("len(", 4));
+    }
+
+    #[test]
+    fn it_completes_after_non_ascii_text() {
+        let mut repl = Repl::with_backend(Backend::Tree);
+        repl.eval(String::from("let double = fn [x] { x * 2 }"));
+
+        assert_eq!((11, vec![String::from("double(")]), repl.complete("print(\"€\"do", 13));
+        assert_eq!((3, vec![String::from("double(")]), repl.complete("€do", 5));
+        assert_eq!(Some(String::from("x)")), repl.hint("\"€\" + double(", 15));
+        assert_eq!(None, repl.hint("print(\"€(", 11));
+    }
+
+    #[test]
+    fn it_fails_loading_with_errors_in_the_file() {
+        let path = env::temp_dir().join("rascal_repl_load_test.rl");
+        fs::write(&path, "let x = 1;\nx + \"a\"").unwrap();
+        let mut repl = Repl::with_backend(Backend::Tree);
+
+        let error = repl.run(format!(":load {}", path.display())).unwrap_err();
+        let rendered = repl.render_to_terminal(error, ":load");
+        assert!(rendered.contains(&format!("{}:2:1", path.display())), "{}", rendered);
+        assert!(rendered.contains("x + \"a\""), "{}", rendered);
+        assert_eq!("1", repl.eval(String::from("x")));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn it_resets_and_quits_the_session() {
+        let mut interpreter = Interpreter::with_backend(Backend::Tree);
+        interpreter.allow_process_access();
+        let mut repl = Repl::with_interpreter(interpreter);
+        repl.eval(String::from("let x = 1"));
+        repl.eval(String::from(":reset"));
+
+        assert_eq!("", repl.eval(String::from(":env")));
+        assert_eq!("Str", repl.eval(String::from(":type env(\"PATH\")")));
+        assert!(!repl.is_finished());
+        repl.eval(String::from(":quit"));
+        assert!(repl.is_finished());
+    }
+
+    #[test]
+    fn it_waits_for_unclosed_blocks_and_groups() {
+        assert!(is_incomplete("while x < 10 {"));
+        assert!(is_incomplete("let f = fn [x] begin\n  x + 1"));
+        assert!(is_incomplete("print((1 + 2)"));
+        assert!(is_incomplete("[1,\n2"));
+        assert!(is_incomplete("\"unterminated"));
+        assert!(!is_incomplete("let f = fn [x] begin x + 1 end"));
+        assert!(!is_incomplete("}"));
+    }
+
+    #[test]
+    fn it_waits_for_trailing_operators() {
+        assert!(is_incomplete("1 +"));
+        assert!(is_incomplete("x and"));
+        assert!(is_incomplete("var x ="));
+        assert!(!is_incomplete("1 + 2"));
+        assert!(!is_incomplete(""));
     }
 }
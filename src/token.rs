@@ -1,3 +1,4 @@
+use error::SyntaxError;
 
 // #Kind
 // Represents a type of a token
@@ -6,12 +7,15 @@ pub enum Kind {
     // expression
     Alphanum,
     Integer,
+    Float,
+    Str,
     Operator,
     GroupBegin,
     GroupEnd,
 
     // Bolean
     Comparison,
+    Not,
     Bolean,
 
     // Reserved
@@ -21,6 +25,8 @@ pub enum Kind {
     FunctionDefine,
     FunctionParamBegin,
     FunctionParamEnd,
+    Rest,
+    Pipe,
     ImmutableDefine,
     MutableDefine,
     Assign,
@@ -28,14 +34,21 @@ pub enum Kind {
     CONST,
     StdOut,
     Return,
+    Break,
+    Continue,
     While,
+    For,
+    In,
     If,
     Else,
 
     // Others
+    Invalid,
     Comment,
     EndLine,
     Separator,
+    Colon,
+    Dot,
     Space,
     EOF
 }
@@ -49,6 +62,11 @@ impl Kind {
                 match value {
                     ';' => Kind::StatementEnd,
                     ',' => Kind::Separator,
+                    '.' => Kind::Dot,
+                    ':' => Kind::Colon,
+                    '{' => Kind::Begin,
+                    '}' => Kind::End,
+                    '|' => Kind::Pipe,
                     '(' => Kind::GroupBegin,
                     ')' => Kind::GroupEnd,
                     '[' => Kind::FunctionParamBegin,
@@ -56,7 +74,9 @@ impl Kind {
                     ' ' => Kind::Space,
                     '\n' => Kind::EndLine,
                     '#' => Kind::Comment,
+                    '"' => Kind::Str,
                     '+'|'-'|'*'|'/'|'%' => Kind::Operator,
+                    '='|'<'|'>'|'!' => Kind::Comparison,
                     '0'|'1'|'2'|'3'|'4'|
                     '5'|'6'|'7'|'8'|'9' => Kind::Integer,
                     _ => Kind::Alphanum
@@ -66,6 +86,16 @@ impl Kind {
         }
     }
 
+    // keywords
+    // The reserved words, as opposed to the reserved symbols
+    pub fn keywords() -> Vec<&'static str> {
+        vec![
+            "fn", "let", "imut", "var", "begin", "end", "return", "break",
+            "continue", "print", "while", "for", "in", "if", "else", "true",
+            "false", "or", "and", "not"
+        ]
+    }
+
     // reserved
     // Retrieve a special kind for reserved keywords from a given string
     pub fn reserved(word: &String) -> Option<Kind> {
@@ -78,34 +108,78 @@ impl Kind {
             "begin" | "{" => Some(Kind::Begin),
             "end" | "}" => Some(Kind::End),
             "return" => Some(Kind::Return),
+            "break" => Some(Kind::Break),
+            "continue" => Some(Kind::Continue),
 
             // System
             "print" => Some(Kind::StdOut),
 
             // Conditionals
             "while" => Some(Kind::While),
+            "for" => Some(Kind::For),
+            "in" => Some(Kind::In),
             "if" => Some(Kind::If),
             "else" => Some(Kind::Else),
             "true"|"false" => Some(Kind::Bolean),
             "or"|"||"|"and"|"&&" => Some(Kind::Comparison),
-            "=="|"!="|">"|"<" => Some(Kind::Comparison),
+            "=="|"!="|">"|"<"|">="|"<=" => Some(Kind::Comparison),
+            "not"|"!" => Some(Kind::Not),
 
             _ => None
         }
     }
 }
 
+// # Span
+// Represents a region of the source code. `start` and `end` are byte offsets
+// while `line` and `column` are 1-based and point to the start of the region
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize
+}
+
+impl Span {
+    // to
+    //
+    // It builds a Span that covers from the start of this span until the
+    // end of the other one. Unknown spans (line 0) are ignored
+    pub fn to(&self, other: Span) -> Span {
+        match (self.is_known(), other.is_known()) {
+            (true, true) => Span { end: other.end, ..*self },
+            (false, _) => other,
+            (_, false) => *self
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        self.line > 0
+    }
+}
+
 // # Token
-// Represents a value and a type inside the system
-#[derive(Debug, Clone, PartialEq)]
+// Represents a value and a type inside the system and where it was found
+// in the source code
+#[derive(Debug, Clone)]
 pub struct Token {
     pub kind: Kind,
-    pub value: String
+    pub value: String,
+    pub span: Span
 }
 
 impl Token {
     pub fn build(kind: Kind, value: String) -> Token {
-        Token { kind: kind, value: value }
+        Token { kind, value, span: Span::default() }
+    }
+}
+
+// The span is not part of a token identity, so tokens can be compared
+// regardless of where they were found
+impl PartialEq for Token {
+    fn eq(&self, other: &Token) -> bool {
+        self.kind == other.kind && self.value == other.value
     }
 }
 
@@ -114,16 +188,22 @@ impl Token {
 // Responsible for interpret a raw String and extract Tokens from it
 #[derive(Clone)]
 pub struct Tokenizer {
-    pub text: String,
     pub position: usize,
+    chars: Vec<char>,
+    offset: usize,
+    line: usize,
+    column: usize,
     current: Option<Token>
 }
 
 impl Tokenizer {
     pub fn new(text: String) -> Self {
         Tokenizer {
-            text: text,
+            chars: text.chars().collect(),
             position: 0,
+            offset: 0,
+            line: 1,
+            column: 1,
             current: None
         }
     }
@@ -131,7 +211,7 @@ impl Tokenizer {
 
 impl Tokenizer {
     pub fn current(&self) -> Option<char> {
-        self.text.chars().nth(self.position)
+        self.chars.get(self.position).cloned()
     }
 
     // next
@@ -150,10 +230,18 @@ impl Tokenizer {
         self.current.clone()
     }
 
-    pub fn peek(&mut self, next: usize) -> Option<Token> {
-        let curr_position = self.position.clone();
-        let next = self.next();
-        self.position = curr_position;
+    // peek
+    //
+    // It reads ahead the token after the current one, skipping the given
+    // amount of tokens, without consuming them
+    pub fn peek(&mut self, skip: usize) -> Option<Token> {
+        let cursor = (self.position, self.offset, self.line, self.column);
+        let next = self.nth(skip);
+        let (position, offset, line, column) = cursor;
+        self.position = position;
+        self.offset = offset;
+        self.line = line;
+        self.column = column;
         next
     }
 
@@ -161,23 +249,154 @@ impl Tokenizer {
     //
     // It is responsible for consume the current Token validating the expected
     // token for the expression sintax
-    pub fn consume(&mut self, expected_kind: Kind) -> Token {
-        if let Some(token) = self.current.clone() {
-            self.current = None;
-            if token.kind != expected_kind {
-                panic!(
-                    "Sintax error: expected token kind {:?} found {:?} at position {}",
-                    expected_kind,
-                    token,
-                    self.position
-                    )
+    pub fn consume(&mut self, expected_kind: Kind) -> Result<Token, SyntaxError> {
+        match self.current.clone() {
+            Some(ref token) if token.kind == expected_kind => {
+                self.current = None;
+                Ok(token.clone())
+            },
+            _ => Err(self.unexpected(vec![expected_kind]))
+        }
+    }
+
+    // unexpected
+    //
+    // It builds a SyntaxError for the current token, which is not one of
+    // the expected kinds
+    pub fn unexpected(&self, expected: Vec<Kind>) -> SyntaxError {
+        let span = match self.current {
+            Some(ref token) => token.span,
+            None => self.span_from(self.offset, self.line, self.column)
+        };
+        SyntaxError::new(expected, self.current.clone(), span)
+    }
+
+    // bump
+    //
+    // It moves to the next char keeping track of its byte offset, line
+    // and column
+    fn bump(&mut self) {
+        if let Some(character) = self.current() {
+            self.offset += character.len_utf8();
+            if character == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
             }
-            return token;
-        } else {
-            panic!("Lexer error: expected {:?} found end of file", expected_kind);
         }
+        self.position += 1;
+    }
+
+    fn span_from(&self, start: usize, line: usize, column: usize) -> Span {
+        Span { start, end: self.offset, line, column }
     }
 }
+impl Tokenizer {
+    // number
+    //
+    // It reads an integer or a float literal. A float has a fraction, as
+    // in `1.5`, an exponent, as in `1e-3`, or both. The dot must be
+    // followed by a digit so `xs.0` or `1..` are not taken as fractions.
+    // Integers must fit in 64 bits unless the bigint feature is enabled
+    fn number(&mut self, first: char) -> (Kind, String) {
+        let mut chars = vec![first];
+        let mut kind = Kind::Integer;
+        self.digits(&mut chars);
+
+        if self.current() == Some('.') && self.is_digit(1) {
+            kind = Kind::Float;
+            chars.push('.');
+            self.bump();
+            self.digits(&mut chars);
+        }
+
+        if let Some(exponent @ 'e') | Some(exponent @ 'E') = self.current() {
+            let signed = match self.chars.get(self.position + 1) {
+                Some(&'+') | Some(&'-') => 1,
+                _ => 0
+            };
+            if self.is_digit(1 + signed) {
+                kind = Kind::Float;
+                chars.push(exponent);
+                self.bump();
+                if signed == 1 {
+                    chars.push(self.current().unwrap());
+                    self.bump();
+                }
+                self.digits(&mut chars);
+            }
+        }
+
+        let value: String = chars.into_iter().collect();
+        if kind == Kind::Integer && !cfg!(feature = "bigint") && value.parse::<i64>().is_err() {
+            return (Kind::Invalid, format!("integer literal {} is too large", value))
+        }
+        (kind, value)
+    }
+
+    fn digits(&mut self, chars: &mut Vec<char>) {
+        while let Some(digit) = self.current().filter(char::is_ascii_digit) {
+            chars.push(digit);
+            self.bump();
+        }
+    }
+
+    fn is_digit(&self, ahead: usize) -> bool {
+        matches!(self.chars.get(self.position + ahead), Some(c) if c.is_ascii_digit())
+    }
+
+    // string
+    //
+    // It reads a string literal until its closing quote, resolving the
+    // escape sequences \n, \t, \", \\ and \u{...}. A malformed literal
+    // results in the reason why it could not be read
+    fn string(&mut self) -> Result<String, String> {
+        let mut chars = vec![];
+        loop {
+            let current = self.current();
+            self.bump();
+            match current {
+                Some('"') => break,
+                Some('\\') => {
+                    let escaped = self.current();
+                    self.bump();
+                    chars.push(self.escape(escaped)?)
+                },
+                Some(value) => chars.push(value),
+                None => return Err(String::from("unterminated string literal"))
+            }
+        }
+        Ok(chars.into_iter().collect())
+    }
+
+    fn escape(&mut self, escaped: Option<char>) -> Result<char, String> {
+        match escaped {
+            Some('n') => Ok('\n'),
+            Some('t') => Ok('\t'),
+            Some('"') => Ok('"'),
+            Some('\\') => Ok('\\'),
+            Some('u') => {
+                if self.current() != Some('{') {
+                    return Err(String::from("expected { after \\u escape"))
+                }
+                self.bump();
+                let mut digits = String::new();
+                while let Some(digit) = self.current() {
+                    self.bump();
+                    if digit == '}' { break }
+                    digits.push(digit);
+                }
+                u32::from_str_radix(&digits, 16).ok()
+                    .and_then(::std::char::from_u32)
+                    .ok_or(format!("invalid unicode escape \\u{{{}}}", digits))
+            },
+            other => Err(format!("unknown escape sequence \\{}",
+                                 other.map(|c| c.to_string()).unwrap_or_default()))
+        }
+    }
+}
+
 impl Iterator for Tokenizer {
     type Item = Token;
 
@@ -185,22 +404,67 @@ impl Iterator for Tokenizer {
         let current = self.current();
         let kind = Kind::classify(&current);
 
-        self.position += 1;
-        match kind {
-            Kind::EOF => None,
+        let (start, line, column) = (self.offset, self.line, self.column);
+        self.bump();
+        let (kind, value) = match kind {
+            Kind::EOF => return None,
 
-            Kind::Space | Kind::EndLine => self.next(),
+            Kind::Space | Kind::EndLine => return self.next(),
 
             Kind::Comment => {
-                while Kind::EndLine != Kind::classify(&self.current()) {
-                    self.position +=1;
+                while self.current().is_some() &&
+                      Kind::EndLine != Kind::classify(&self.current()) {
+                    self.bump();
                 };
 
-                self.next()
+                return self.next()
             },
 
-            Kind::GroupBegin | Kind::GroupEnd | Kind::Operator =>
-                Some(Token::build(kind, format!("{}", current.unwrap()))),
+            Kind::GroupBegin | Kind::GroupEnd | Kind::Operator |
+            Kind::FunctionParamBegin | Kind::FunctionParamEnd |
+            Kind::Begin | Kind::End | Kind::Colon | Kind::Separator =>
+                (kind, format!("{}", current.unwrap())),
+
+            Kind::Dot => {
+                if self.current() == Some('.') {
+                    self.bump();
+                    (Kind::Rest, String::from(".."))
+                } else {
+                    (kind, String::from("."))
+                }
+            },
+
+            // An operator is one character, or two when the second is `=`
+            // and they make one of `==`, `!=`, `<=` or `>=`. So `!` followed
+            // by anything else is a unary not, e.g. `x==!y`
+            Kind::Comparison => {
+                let mut word = current.unwrap().to_string();
+                if self.current() == Some('=') && Kind::reserved(&format!("{}=", word)).is_some() {
+                    word.push('=');
+                    self.bump();
+                }
+
+                match Kind::reserved(&word) {
+                    Some(reserved) => (reserved, word),
+                    _ => (Kind::Invalid, format!("unknown operator {}", word))
+                }
+            },
+
+            Kind::Pipe => {
+                if self.current() == Some('|') {
+                    self.bump();
+                    (Kind::Comparison, String::from("||"))
+                } else {
+                    (kind, String::from("|"))
+                }
+            },
+
+            Kind::Integer => self.number(current.unwrap()),
+
+            Kind::Str => match self.string() {
+                Ok(value) => (kind, value),
+                Err(reason) => (Kind::Invalid, reason)
+            },
 
             Kind::Alphanum => {
                 let mut chars = vec![current.unwrap()];
@@ -209,15 +473,15 @@ impl Iterator for Tokenizer {
 
                 while kindnext == kind || kindnext == Kind::Integer {
                     chars.push(next.unwrap());
-                    self.position += 1;
+                    self.bump();
                     next = self.current();
                     kindnext = Kind::classify(&next);
                 }
 
                 let word: String = chars.clone().into_iter().collect();
                 match Kind::reserved(&word) {
-                    Some(reserved) => Some(Token{ kind: reserved, value: word }),
-                    _ => Some(Token{ kind: Kind::ID, value: word })
+                    Some(reserved) => (reserved, word),
+                    _ => (Kind::ID, word)
                 }
             }
 
@@ -228,15 +492,17 @@ impl Iterator for Tokenizer {
 
                 while kindnext == kind {
                     chars.push(next.unwrap());
-                    self.position += 1;
+                    self.bump();
 
                     next = self.current();
                     kindnext = Kind::classify(&next);
                 }
 
-                Some(Token::build(kind, chars.into_iter().collect()))
+                (kind, chars.into_iter().collect())
             }
-        }
+        };
+
+        Some(Token { kind, value, span: self.span_from(start, line, column) })
     }
 }
 
@@ -247,15 +513,15 @@ fn it_generate_tokens() {
 
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Integer, value: String::from("5") })
+        Some(Token::build(Kind::Integer, String::from("5")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Operator, value: String::from("+") })
+        Some(Token::build(Kind::Operator, String::from("+")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Integer, value: String::from("1") })
+        Some(Token::build(Kind::Integer, String::from("1")))
     );
     assert_eq!( tokens.next(), None);
 }
@@ -267,15 +533,15 @@ fn it_ignores_empty_spaces() {
 
     assert_eq!(
         tokens.next().unwrap(),
-        Token { kind: Kind::Integer, value: String::from("5") }
+        Token::build(Kind::Integer, String::from("5"))
     );
     assert_eq!(
         tokens.next().unwrap(),
-        Token { kind: Kind::Operator, value: String::from("+") }
+        Token::build(Kind::Operator, String::from("+"))
     );
     assert_eq!(
         tokens.next().unwrap(),
-        Token { kind: Kind::Integer, value: String::from("1") }
+        Token::build(Kind::Integer, String::from("1"))
     );
 }
 
@@ -286,7 +552,7 @@ fn it_ignores_a_comment() {
 
     assert_eq!(
         tokens.next().unwrap(),
-        Token { kind: Kind::Integer, value: String::from("5") }
+        Token::build(Kind::Integer, String::from("5"))
     );
 }
 
@@ -297,18 +563,43 @@ fn it_acepts_high_numbers() {
 
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Integer, value: String::from("21") })
+        Some(Token::build(Kind::Integer, String::from("21")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Operator, value: String::from("+") })
+        Some(Token::build(Kind::Operator, String::from("+")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Integer, value: String::from("1102") })
+        Some(Token::build(Kind::Integer, String::from("1102")))
     );
 }
 
+#[test]
+fn it_accepts_float_numbers() {
+    let text = "1.5 0.25e2 1e-3 2E+4";
+    let kinds: Vec<(Kind, String)> = Tokenizer::new(String::from(text))
+        .map(|token| (token.kind, token.value))
+        .collect();
+
+    assert_eq!(vec![
+        (Kind::Float, String::from("1.5")),
+        (Kind::Float, String::from("0.25e2")),
+        (Kind::Float, String::from("1e-3")),
+        (Kind::Float, String::from("2E+4")),
+    ], kinds);
+}
+
+#[test]
+fn it_keeps_dots_not_followed_by_digits() {
+    let text = "1.x 1..";
+    let kinds: Vec<Kind> = Tokenizer::new(String::from(text)).map(|token| token.kind).collect();
+
+    assert_eq!(vec![
+        Kind::Integer, Kind::Dot, Kind::ID, Kind::Integer, Kind::Rest
+    ], kinds);
+}
+
 #[test]
 fn it_acepts_grouped_expressions() {
     let text = "(1)*1";
@@ -316,15 +607,15 @@ fn it_acepts_grouped_expressions() {
 
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::GroupBegin, value: String::from("(") })
+        Some(Token::build(Kind::GroupBegin, String::from("(")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Integer, value: String::from("1") })
+        Some(Token::build(Kind::Integer, String::from("1")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::GroupEnd, value: String::from(")") })
+        Some(Token::build(Kind::GroupEnd, String::from(")")))
     );
 }
 
@@ -333,18 +624,12 @@ fn it_accepts_statements() {
     let text = "begin x = 1; end";
     let mut tokens = Tokenizer::new(String::from(text));
 
-    assert_eq!(tokens.next(), Some(Token { kind: Kind::Begin,
-                                           value: String::from("begin")}));
-    assert_eq!(tokens.next(), Some(Token { kind: Kind::ID,
-                                           value: String::from("x")}));
-    assert_eq!(tokens.next(), Some(Token { kind: Kind::Assign,
-                                           value: String::from("=")}));
-    assert_eq!(tokens.next(), Some(Token { kind: Kind::Integer,
-                                           value: String::from("1")}));
-    assert_eq!(tokens.next(), Some(Token { kind: Kind::StatementEnd,
-                                           value: String::from(";") }));
-    assert_eq!(tokens.next(), Some(Token { kind: Kind::End,
-                                           value: String::from("end") }));
+    assert_eq!(tokens.next(), Some(Token::build(Kind::Begin, String::from("begin"))));
+    assert_eq!(tokens.next(), Some(Token::build(Kind::ID, String::from("x"))));
+    assert_eq!(tokens.next(), Some(Token::build(Kind::Assign, String::from("="))));
+    assert_eq!(tokens.next(), Some(Token::build(Kind::Integer, String::from("1"))));
+    assert_eq!(tokens.next(), Some(Token::build(Kind::StatementEnd, String::from(";"))));
+    assert_eq!(tokens.next(), Some(Token::build(Kind::End, String::from("end"))));
 }
 
 #[test]
@@ -354,15 +639,15 @@ fn it_accepts_comparison_tokens() {
 
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Bolean, value: String::from("true") })
+        Some(Token::build(Kind::Bolean, String::from("true")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Comparison, value: String::from("==") })
+        Some(Token::build(Kind::Comparison, String::from("==")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Bolean, value: String::from("false") })
+        Some(Token::build(Kind::Bolean, String::from("false")))
     );
     assert_eq!( tokens.next(), None);
 }
@@ -374,39 +659,39 @@ fn it_accepts_complex_comparison_tokens() {
 
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Bolean, value: String::from("true") })
+        Some(Token::build(Kind::Bolean, String::from("true")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Comparison, value: String::from("==") })
+        Some(Token::build(Kind::Comparison, String::from("==")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Bolean, value: String::from("false") })
+        Some(Token::build(Kind::Bolean, String::from("false")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Comparison, value: String::from("and") })
+        Some(Token::build(Kind::Comparison, String::from("and")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Bolean, value: String::from("true") })
+        Some(Token::build(Kind::Bolean, String::from("true")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Comparison, value: String::from("or") })
+        Some(Token::build(Kind::Comparison, String::from("or")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Bolean, value: String::from("false") })
+        Some(Token::build(Kind::Bolean, String::from("false")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Comparison, value: String::from("!=") })
+        Some(Token::build(Kind::Comparison, String::from("!=")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Bolean, value: String::from("false") })
+        Some(Token::build(Kind::Bolean, String::from("false")))
     );
 }
 
@@ -417,7 +702,7 @@ fn it_acepts_if_else_statements() {
 
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::If, value: String::from("if") })
+        Some(Token::build(Kind::If, String::from("if")))
     );
 
     tokens.next(); // x
@@ -430,7 +715,7 @@ fn it_acepts_if_else_statements() {
 
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Else, value: String::from("else") })
+        Some(Token::build(Kind::Else, String::from("else")))
     );
 }
 
@@ -440,39 +725,39 @@ fn it_acepts_function_definitions() {
     let mut tokens = Tokenizer::new(String::from(text));
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::FunctionDefine, value: String::from("fn") })
+        Some(Token::build(Kind::FunctionDefine, String::from("fn")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::ID, value: String::from("f") })
+        Some(Token::build(Kind::ID, String::from("f")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Assign, value: String::from("=") })
+        Some(Token::build(Kind::Assign, String::from("=")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::FunctionParamBegin, value: String::from("[") })
+        Some(Token::build(Kind::FunctionParamBegin, String::from("[")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::ID, value: String::from("x") })
+        Some(Token::build(Kind::ID, String::from("x")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Separator, value: String::from(",") })
+        Some(Token::build(Kind::Separator, String::from(",")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::ID, value: String::from("y") })
+        Some(Token::build(Kind::ID, String::from("y")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::FunctionParamEnd, value: String::from("]") })
+        Some(Token::build(Kind::FunctionParamEnd, String::from("]")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Begin, value: String::from("{") })
+        Some(Token::build(Kind::Begin, String::from("{")))
     );
 }
 
@@ -482,27 +767,27 @@ fn it_acepts_function_calls() {
     let mut tokens = Tokenizer::new(String::from(text));
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::ID, value: String::from("foo") })
+        Some(Token::build(Kind::ID, String::from("foo")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::GroupBegin, value: String::from("(") })
+        Some(Token::build(Kind::GroupBegin, String::from("(")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::ID, value: String::from("x") })
+        Some(Token::build(Kind::ID, String::from("x")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Separator, value: String::from(",") })
+        Some(Token::build(Kind::Separator, String::from(",")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::ID, value: String::from("y") })
+        Some(Token::build(Kind::ID, String::from("y")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::GroupEnd, value: String::from(")") })
+        Some(Token::build(Kind::GroupEnd, String::from(")")))
     );
 }
 
@@ -512,27 +797,194 @@ fn it_accepts_std_output() {
     let mut tokens = Tokenizer::new(String::from(text));
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::StdOut, value: String::from("print") })
+        Some(Token::build(Kind::StdOut, String::from("print")))
+    );
+    assert_eq!(
+        tokens.next(),
+        Some(Token::build(Kind::GroupBegin, String::from("(")))
+    );
+    assert_eq!(
+        tokens.next(),
+        Some(Token::build(Kind::ID, String::from("x")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::GroupBegin, value: String::from("(") })
+        Some(Token::build(Kind::Operator, String::from("+")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::ID, value: String::from("x") })
+        Some(Token::build(Kind::ID, String::from("y")))
+    );
+    assert_eq!(
+        tokens.next(),
+        Some(Token::build(Kind::GroupEnd, String::from(")")))
+    );
+}
+
+
+#[test]
+fn it_accepts_string_literals() {
+    let text = "let x = \"foo bar\";";
+    let mut tokens = Tokenizer::new(String::from(text));
+
+    tokens.next(); // let
+    tokens.next(); // x
+    tokens.next(); // =
+    assert_eq!(
+        tokens.next(),
+        Some(Token::build(Kind::Str, String::from("foo bar")))
     );
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::Operator, value: String::from("+") })
+        Some(Token::build(Kind::StatementEnd, String::from(";")))
     );
+}
+
+#[test]
+fn it_accepts_string_escape_sequences() {
+    let text = r#""a\tb\n\"c\" \\ \u{48}\u{e9}""#;
+    let mut tokens = Tokenizer::new(String::from(text));
+
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::ID, value: String::from("y") })
+        Some(Token::build(Kind::Str, String::from("a\tb\n\"c\" \\ H\u{e9}")))
     );
+    assert_eq!( tokens.next(), None);
+}
+
+#[test]
+fn it_produces_invalid_token_for_malformed_strings() {
+    let text = "\"foo\\q\"";
+    let mut tokens = Tokenizer::new(String::from(text));
+
     assert_eq!(
         tokens.next(),
-        Some(Token { kind: Kind::GroupEnd, value: String::from(")") })
+        Some(Token::build(Kind::Invalid, String::from("unknown escape sequence \\q")))
     );
 }
 
+#[test]
+fn it_ignores_a_comment_at_end_of_file() {
+    let text = "5 # foo";
+    let mut tokens = Tokenizer::new(String::from(text));
+
+    assert_eq!(
+        tokens.next(),
+        Some(Token::build(Kind::Integer, String::from("5")))
+    );
+    assert_eq!( tokens.next(), None);
+}
+
+#[test]
+fn it_tracks_token_spans() {
+    let text = "let é = \"ü\";\n  x";
+    let mut tokens = Tokenizer::new(String::from(text));
+
+    let spans: Vec<Span> = tokens.by_ref().map(|token| token.span).collect();
+    assert_eq!(Span { start: 0, end: 3, line: 1, column: 1 }, spans[0]);
+    assert_eq!(Span { start: 4, end: 6, line: 1, column: 5 }, spans[1]);
+    assert_eq!(Span { start: 9, end: 13, line: 1, column: 9 }, spans[3]);
+    assert_eq!(Span { start: 17, end: 18, line: 2, column: 3 }, spans[5]);
+}
+
+#[test]
+fn it_generates_rest_tokens() {
+    let text = "[x, ..rest]";
+    let tokens: Vec<Kind> = Tokenizer::new(String::from(text))
+        .map(|token| token.kind)
+        .collect();
+
+    assert_eq!(vec![
+        Kind::FunctionParamBegin, Kind::ID, Kind::Separator,
+        Kind::Rest, Kind::ID, Kind::FunctionParamEnd
+    ], tokens);
+}
+
+#[test]
+fn it_generates_pipe_tokens() {
+    let text = "|x| x || y";
+    let tokens: Vec<Token> = Tokenizer::new(String::from(text)).collect();
+
+    assert_eq!(vec![
+        Token::build(Kind::Pipe, String::from("|")),
+        Token::build(Kind::ID, String::from("x")),
+        Token::build(Kind::Pipe, String::from("|")),
+        Token::build(Kind::ID, String::from("x")),
+        Token::build(Kind::Comparison, String::from("||")),
+        Token::build(Kind::ID, String::from("y")),
+    ], tokens);
+}
+
+#[test]
+fn it_generates_operators_without_spaces() {
+    let text = "x==1 != !y>=2=z";
+    let tokens: Vec<Token> = Tokenizer::new(String::from(text)).collect();
+
+    assert_eq!(vec![
+        Token::build(Kind::ID, String::from("x")),
+        Token::build(Kind::Comparison, String::from("==")),
+        Token::build(Kind::Integer, String::from("1")),
+        Token::build(Kind::Comparison, String::from("!=")),
+        Token::build(Kind::Not, String::from("!")),
+        Token::build(Kind::ID, String::from("y")),
+        Token::build(Kind::Comparison, String::from(">=")),
+        Token::build(Kind::Integer, String::from("2")),
+        Token::build(Kind::Assign, String::from("=")),
+        Token::build(Kind::ID, String::from("z")),
+    ], tokens);
+}
+
+#[test]
+fn it_generates_unary_not_after_operators() {
+    let kinds = |text: &str| -> Vec<Kind> {
+        Tokenizer::new(String::from(text)).map(|token| token.kind).collect()
+    };
+
+    assert_eq!(vec![Kind::Not, Kind::Not, Kind::Bolean], kinds("!!true"));
+    assert_eq!(vec![Kind::ID, Kind::Comparison, Kind::Not, Kind::ID], kinds("x==!y"));
+    assert_eq!(vec![Kind::ID, Kind::Comparison, Kind::Not, Kind::ID], kinds("a!=!b"));
+    assert_eq!(vec![Kind::Comparison, Kind::Assign], kinds("==="));
+}
+
+#[test]
+fn it_generates_one_token_per_bracket() {
+    let text = "[[1]]";
+    let tokens: Vec<Kind> = Tokenizer::new(String::from(text))
+        .map(|token| token.kind)
+        .collect();
+
+    assert_eq!(vec![
+        Kind::FunctionParamBegin, Kind::FunctionParamBegin, Kind::Integer,
+        Kind::FunctionParamEnd, Kind::FunctionParamEnd
+    ], tokens);
+}
+
+#[test]
+fn it_generates_map_tokens() {
+    let text = "{\"a\": {x}}";
+    let tokens: Vec<Kind> = Tokenizer::new(String::from(text))
+        .map(|token| token.kind)
+        .collect();
+
+    assert_eq!(vec![
+        Kind::Begin, Kind::Str, Kind::Colon, Kind::Begin, Kind::ID,
+        Kind::End, Kind::End
+    ], tokens);
+}
+
+#[test]
+fn it_peeks_tokens_ahead() {
+    let text = "a b c";
+    let mut tokens = Tokenizer::new(String::from(text));
+    tokens.advance();
+
+    assert_eq!(Some(Token::build(Kind::ID, String::from("c"))), tokens.peek(1));
+    assert_eq!(Some(Token::build(Kind::ID, String::from("b"))), tokens.peek(0));
+}
+
+#[test]
+fn it_lists_only_reserved_keywords() {
+    for keyword in Kind::keywords() {
+        assert!(Kind::reserved(&String::from(keyword)).is_some(), "{}", keyword);
+    }
+}
@@ -0,0 +1,492 @@
+// This module contains the Vm, which runs the bytecode generated by the
+// Compiler. It is a stack machine: instructions take their operands from
+// the top of the value stack and push their results on it, and each call
+// has a frame with the slots of the local variables of the function
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+use token::Span;
+use compiler::{Prototype, Chunk, Instruction, Variable, Capture};
+use primitive::Type;
+use frame::{Frame, Binding};
+use error::RuntimeError;
+use diagnostics::suggest;
+use interpreter::{binary_operation, binary_comparison, unary_operation, check_arity};
+
+// Function
+//
+// A function value of the Vm: the compiled function and the variables it
+// captured from the functions around it when it was created
+pub struct Function {
+    pub prototype: Rc<Prototype>,
+    pub captures: Vec<Binding>
+}
+
+// The captures may contain the function itself (recursion), so they are
+// not printed
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("min", &self.prototype.min)
+            .field("max", &self.prototype.max)
+            .finish()
+    }
+}
+
+struct CallFrame {
+    function: Rc<Function>,
+    ip: usize,
+    // The position of the stack where the function result goes
+    base: usize,
+    argc: usize,
+    slots: Vec<Option<Binding>>
+}
+
+// run
+//
+// It runs the compiled main program resulting in the value of its last
+// statement. Its globals are the variables of the given frame and it can't
+// nest more than `max_depth` function calls
+pub fn run(prototype: Prototype, global: &Frame, max_depth: usize)
+    -> Result<Type, RuntimeError> {
+    let slots = vec![None; prototype.slots];
+    let main = Function { prototype: Rc::new(prototype), captures: vec![] };
+    let mut vm = Vm {
+        stack: vec![],
+        frames: vec![CallFrame { function: Rc::new(main), ip: 0, base: 0, argc: 0, slots }],
+        global,
+        max_depth
+    };
+    vm.execute()
+}
+
+struct Vm<'a> {
+    stack: Vec<Type>,
+    frames: Vec<CallFrame>,
+    global: &'a Frame,
+    max_depth: usize
+}
+
+fn cell(value: Type) -> Binding {
+    Rc::new(RefCell::new(value))
+}
+
+impl<'a> Vm<'a> {
+    fn execute(&mut self) -> Result<Type, RuntimeError> {
+        loop {
+            let function = self.frame().function.clone();
+            let ip = self.frame().ip;
+            self.frame().ip += 1;
+
+            let chunk = &function.prototype.chunk;
+            match self.step(&chunk.code[ip], chunk, &function) {
+                Ok(Some(result)) => return Ok(result),
+                Ok(None) => (),
+                Err(error) => return Err(error.at(chunk.spans[ip]))
+            }
+        }
+    }
+
+    fn frame(&mut self) -> &mut CallFrame {
+        self.frames.last_mut().expect("there is always a running function")
+    }
+
+    fn pop(&mut self) -> Type {
+        self.stack.pop().expect("instructions have their operands on the stack")
+    }
+
+    fn peek(&self) -> &Type {
+        self.stack.last().expect("instructions have their operands on the stack")
+    }
+
+    fn split(&mut self, amount: usize) -> Vec<Type> {
+        let at = self.stack.len() - amount;
+        self.stack.split_off(at)
+    }
+
+    // local
+    //
+    // The binding of a slot of the running function. A slot that was not
+    // defined yet is Nil
+    fn local(&mut self, slot: usize) -> Binding {
+        self.frame().slots[slot].get_or_insert_with(|| cell(Type::Nil)).clone()
+    }
+
+    fn binding(&mut self, variable: Variable, function: &Function) -> Option<Binding> {
+        match variable {
+            Variable::Local(slot) => Some(self.local(slot)),
+            Variable::Capture(index) => Some(function.captures[index].clone()),
+            Variable::Global { .. } => None
+        }
+    }
+
+    // similar_name
+    //
+    // It looks for a name similar to the given one among the globals and
+    // the variables in scope where it was used
+    fn similar_name(&self, name: &str, chunk: &Chunk, visible: usize) -> Option<String> {
+        suggest(name, self.global.names().iter().chain(chunk.visible[visible].iter()))
+    }
+
+    // step
+    //
+    // It runs a single instruction. It results in the program result when
+    // the main program returns
+    fn step(&mut self, instruction: &Instruction, chunk: &Chunk, function: &Function)
+        -> Result<Option<Type>, RuntimeError> {
+        match *instruction {
+            Instruction::Constant(index) => self.stack.push(chunk.constants[index].clone()),
+
+            Instruction::Nil => self.stack.push(Type::Nil),
+
+            Instruction::Pop => {
+                self.pop();
+            },
+
+            Instruction::Dup => {
+                let value = self.peek().clone();
+                self.stack.push(value);
+            },
+
+            Instruction::Get(Variable::Global { name, visible }) => {
+                let name = &chunk.names[name];
+                match self.global.get(name) {
+                    Some(value) => self.stack.push(value),
+                    None => return Err(RuntimeError::UndefinedVariable {
+                        name: name.clone(),
+                        suggestion: self.similar_name(name, chunk, visible),
+                        span: Default::default()
+                    })
+                }
+            },
+
+            Instruction::Get(variable) => {
+                let binding = self.binding(variable, function).expect("a local variable");
+                let value = binding.borrow().clone();
+                self.stack.push(value);
+            },
+
+            Instruction::Set(variable) => {
+                let value = self.peek().clone();
+                match (self.binding(variable, function), variable) {
+                    (Some(binding), _) => *binding.borrow_mut() = value,
+                    (None, Variable::Global { name, .. }) => {
+                        self.global.set(&chunk.names[name], value);
+                    },
+                    _ => unreachable!("variables are locals, captures or globals")
+                }
+            },
+
+            Instruction::Define(Variable::Global { name, .. }, mutable) => {
+                let value = self.pop();
+                let name = chunk.names[name].clone();
+                if self.global.has(&name) {
+                    return Err(RuntimeError::AlreadyDefined { name, span: Default::default() })
+                }
+                match mutable {
+                    true => self.global.define(name, value),
+                    false => self.global.define_imutable(name, value)
+                }
+            },
+
+            Instruction::Define(Variable::Local(slot), _) => {
+                let value = self.pop();
+                self.frame().slots[slot] = Some(cell(value));
+            },
+
+            Instruction::Define(Variable::Capture(_), _) =>
+                unreachable!("captured variables are defined by their function"),
+
+            Instruction::CheckAssign(Variable::Global { name, visible }) => {
+                let name = chunk.names[name].clone();
+                if self.global.get(&name).is_none() {
+                    let suggestion = self.similar_name(&name, chunk, visible);
+                    return Err(RuntimeError::UndeclaredReassign {
+                        name, suggestion, span: Default::default()
+                    })
+                }
+                if self.global.is_imutable(&name) {
+                    return Err(RuntimeError::ImmutableReassign { name, span: Default::default() })
+                }
+            },
+
+            Instruction::CheckAssign(_) => (),
+
+            Instruction::CheckUndefined(name) => {
+                if self.global.has(&chunk.names[name]) {
+                    let name = chunk.names[name].clone();
+                    return Err(RuntimeError::AlreadyDefined { name, span: Default::default() })
+                }
+            },
+
+            Instruction::SetIndex(variable, depth) => {
+                let value = self.pop();
+                let path = self.split(depth);
+                let changed = match (self.binding(variable, function), variable) {
+                    (Some(binding), _) => binding.borrow_mut().set_index(&path, value.clone()),
+                    (None, Variable::Global { name, .. }) => self.global
+                        .update(&chunk.names[name], |current| current.set_index(&path, value.clone()))
+                        .unwrap_or(Ok(())),
+                    _ => unreachable!("variables are locals, captures or globals")
+                };
+                changed?;
+                self.stack.push(value);
+            },
+
+            Instruction::CheckCallable(name) => match *self.peek() {
+                Type::Compiled(_) | Type::NativeFunc(_) => (),
+                _ => return Err(RuntimeError::NotCallable {
+                    name: chunk.names[name].clone(),
+                    span: Default::default()
+                })
+            },
+
+            Instruction::CheckArity(name, given) => {
+                let arity = match *self.peek() {
+                    Type::Compiled(ref callee) => (callee.prototype.min, callee.prototype.max),
+                    Type::NativeFunc(ref native) => (native.min, native.max),
+                    _ => unreachable!("the callee was checked")
+                };
+                check_arity(chunk.names[name].clone(), arity, given, Default::default())?;
+            },
+
+            Instruction::Call(_, given) => {
+                let args = self.split(given);
+                match self.pop() {
+                    Type::NativeFunc(native) => {
+                        let result = (native.func)(&args)?;
+                        self.stack.push(result);
+                    },
+                    Type::Compiled(callee) => {
+                        if self.frames.len() > self.max_depth {
+                            return Err(RuntimeError::StackOverflow {
+                                depth: self.max_depth,
+                                trace: self.trace(),
+                                span: Span::default()
+                            })
+                        }
+                        self.call(callee, args)
+                    },
+                    _ => unreachable!("the callee was checked")
+                }
+            },
+
+            Instruction::TailCall(_, given) => {
+                let args = self.split(given);
+                match self.pop() {
+                    Type::NativeFunc(native) => {
+                        let result = (native.func)(&args)?;
+                        self.stack.push(result);
+                    },
+                    Type::Compiled(callee) => {
+                        // The callee takes the place of the running function,
+                        // so the call depth stays the same
+                        let finished = self.frames.pop().expect("there is always a running function");
+                        self.stack.truncate(finished.base);
+                        self.call(callee, args)
+                    },
+                    _ => unreachable!("the callee was checked")
+                }
+            },
+
+            Instruction::Closure(index) => {
+                let prototype = chunk.functions[index].clone();
+                let mut captures = vec![];
+                for capture in &prototype.captures {
+                    captures.push(match *capture {
+                        Capture::Local(slot) => self.local(slot),
+                        Capture::Parent(index) => function.captures[index].clone()
+                    });
+                }
+                self.stack.push(Type::Compiled(Rc::new(Function { prototype, captures })));
+            },
+
+            Instruction::Return => {
+                let result = self.pop();
+                let finished = self.frames.pop().expect("there is always a running function");
+                if self.frames.is_empty() {
+                    return Ok(Some(result))
+                }
+                self.stack.truncate(finished.base);
+                self.stack.push(result);
+            },
+
+            Instruction::Jump(target) => self.frame().ip = target,
+
+            Instruction::JumpIfNotTrue(target) => {
+                if self.pop() != Type::Bool(true) {
+                    self.frame().ip = target;
+                }
+            },
+
+            Instruction::JumpIfGiven(param, target) => {
+                if param < self.frame().argc {
+                    self.frame().ip = target;
+                }
+            },
+
+            Instruction::Binary(ref operator) => {
+                let right = self.pop();
+                let left = self.pop();
+                self.stack.push(binary_operation(left, operator, right)?);
+            },
+
+            Instruction::Comparison(ref operator) => {
+                let right = self.pop();
+                let left = self.pop();
+                self.stack.push(binary_comparison(left, operator, right)?);
+            },
+
+            Instruction::Unary(ref operator) => {
+                let operand = self.pop();
+                self.stack.push(unary_operation(operator, operand)?);
+            },
+
+            Instruction::List(length) => {
+                let items = self.split(length);
+                self.stack.push(Type::List(items));
+            },
+
+            Instruction::Map(ref keys) => {
+                let values = self.split(keys.len());
+                let entries: BTreeMap<String, Type> = keys.iter().cloned().zip(values).collect();
+                self.stack.push(Type::Map(entries));
+            },
+
+            Instruction::Index => {
+                let index = self.pop();
+                let target = self.pop();
+                self.stack.push(target.index(&index)?);
+            },
+
+            Instruction::Iterate(slot) => {
+                let items = self.pop().iterate()?;
+                let frame = self.frame();
+                frame.slots[slot] = Some(cell(Type::List(items)));
+                frame.slots[slot + 1] = Some(cell(Type::Int(0)));
+            },
+
+            Instruction::Next(slot, exit) => {
+                let (items, position) = (self.local(slot), self.local(slot + 1));
+                let current = match *position.borrow() {
+                    Type::Int(position) => position as usize,
+                    _ => unreachable!("the position of an iteration is an Int")
+                };
+                let item = match *items.borrow() {
+                    Type::List(ref items) => items.get(current).cloned(),
+                    _ => unreachable!("the items of an iteration are a List")
+                };
+                match item {
+                    Some(item) => {
+                        *position.borrow_mut() = Type::Int(current as i64 + 1);
+                        self.stack.push(item);
+                    },
+                    None => self.frame().ip = exit
+                }
+            },
+
+            Instruction::Print => {
+                let value = self.pop();
+                println!("{}", value);
+                self.stack.push(Type::Nil);
+            },
+
+            Instruction::Fail(index) => return Err(chunk.errors[index].clone())
+        }
+        Ok(None)
+    }
+
+    // trace
+    //
+    // The name and the span of the running calls, from the innermost. Every
+    // function is running a call: the instruction before its next one
+    fn trace(&self) -> Vec<(String, Span)> {
+        self.frames.iter().rev().map(|caller| {
+            let chunk = &caller.function.prototype.chunk;
+            match chunk.code[caller.ip - 1] {
+                Instruction::Call(name, _) => (chunk.names[name].clone(), chunk.spans[caller.ip - 1]),
+                _ => unreachable!("functions are called by the Call instruction")
+            }
+        }).collect()
+    }
+
+    // call
+    //
+    // It starts running a function with the given arguments in its first
+    // slots. A rest parameter takes the list of the remaining ones
+    fn call(&mut self, function: Rc<Function>, args: Vec<Type>) {
+        let argc = args.len();
+        let mut slots = vec![None; function.prototype.slots];
+        let mut args = args.into_iter();
+        let fixed = function.prototype.rest.unwrap_or(argc);
+        for (slot, arg) in args.by_ref().take(fixed).enumerate() {
+            slots[slot] = Some(cell(arg));
+        }
+        if let Some(rest) = function.prototype.rest {
+            slots[rest] = Some(cell(Type::List(args.collect())));
+        }
+
+        let base = self.stack.len();
+        self.frames.push(CallFrame { function, ip: 0, base, argc, slots });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use compiler::Compiler;
+    use frame::Frame;
+    use parser::Parser;
+    use primitive::Type;
+    use token::Tokenizer;
+    use vm::run;
+
+    fn eval(source: &str) -> Type {
+        let tree = Parser::new(Tokenizer::new(String::from(source))).parse().unwrap();
+        run(Compiler::compile(tree), &Frame::new(), 100).unwrap()
+    }
+
+    #[test]
+    fn it_runs_expressions() {
+        assert_eq!(Type::Int(7), eval("1 + 2 * 3"));
+        assert_eq!(Type::Bool(true), eval("1 < 2 and not false"));
+    }
+
+    #[test]
+    fn it_keeps_globals_in_the_given_frame() {
+        let global = Frame::new();
+        let tree = Parser::new(Tokenizer::new(String::from("var x = 1; x = x + 1"))).parse().unwrap();
+        run(Compiler::compile(tree), &global, 100).unwrap();
+
+        assert_eq!(Some(Type::Int(2)), global.get("x"));
+    }
+
+    #[test]
+    fn it_runs_closures_capturing_their_variables() {
+        let source = "
+            let counter = fn [] { var count = 0; fn [] { count = count + 1 } };
+            let next = counter();
+            next(); next(); next()
+        ";
+        assert_eq!(Type::Int(3), eval(source));
+    }
+
+    #[test]
+    fn it_runs_loops_with_break_and_continue() {
+        let source = "
+            var total = 0;
+            for x in range(10) {
+                if x == 5 { break };
+                if x % 2 == 0 { continue };
+                total = total + x
+            };
+            total
+        ";
+        let tree = Parser::new(Tokenizer::new(String::from(source))).parse().unwrap();
+        let global = Frame::new();
+        ::builtins::define(&global);
+
+        assert_eq!(Ok(Type::Int(4)), run(Compiler::compile(tree), &global, 100));
+    }
+}
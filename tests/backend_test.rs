@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod backends {
+    extern crate rascal;
+    use self::rascal::{Interpreter, Backend};
+    use self::rascal::diagnostics::Diagnostic;
+
+    fn eval(backend: Backend, source: &str) -> String {
+        match Interpreter::with_backend(backend).eval(source) {
+            Ok(result) => result.to_string(),
+            Err(error) => error.to_string()
+        }
+    }
+
+    fn assert_same(source: &str) {
+        assert_eq!(eval(Backend::Tree, source), eval(Backend::Vm, source), "{}", source);
+    }
+
+    #[test]
+    fn it_gives_the_same_results() {
+        assert_same("1 + 2 * 3 - 4 / 2");
+        assert_same("var x = 1; { var y = x + 1; x = y * 10 }; x");
+        assert_same("let fib = fn [n] { if n < 2 { n else fib(n - 1) + fib(n - 2) } }; fib(10)");
+        assert_same("let f = fn [a, b = a + 1, ..rest] { [a, b, rest] }; [f(1), f(1, 2, 3, 4)]");
+        assert_same("var m = {\"a\": [[1, 2], [3]]}; m[\"a\"][0][1] = 9; m");
+        assert_same("var t = 0; for i in range(10) { if i == 7 { break }; t = t + i }; t");
+    }
+
+    #[test]
+    fn it_gives_the_same_errors() {
+        assert_same("let x = 1; x = 2");
+        assert_same("var total = 1; totl + 1");
+        assert_same("{ let y = 1; let y = 2 }");
+        assert_same("let f = fn [a] { a }; f(1, 2)");
+        assert_same("1 + \"a\"");
+        assert_same("break");
+    }
+
+    #[test]
+    fn it_runs_deeply_nested_sources() {
+        let source = format!("{}1{}", "[(".repeat(120), ")]".repeat(120));
+        assert_same(&source);
+        assert_same(&format!("{}1{}", "(".repeat(300), ")".repeat(300)));
+    }
+
+    #[test]
+    fn it_suggests_the_same_name_for_misspelled_functions() {
+        let source = "let sum = fn [a, b] { a + b }; summ(1, 2)";
+        let render = |backend| match Interpreter::with_backend(backend).eval(source) {
+            Ok(result) => result.to_string(),
+            Err(error) => Diagnostic::from(error).render("test", source, false)
+        };
+
+        assert!(render(Backend::Tree).contains("help: did you mean `sum`?"));
+        assert_eq!(render(Backend::Tree), render(Backend::Vm));
+        assert_same("let x = 1; x(2)");
+        assert_same("lenn([1])");
+    }
+
+    #[test]
+    fn it_shares_closures_between_calls() {
+        let source = "
+            let counter = fn [] { var count = 0; fn [] { count = count + 1 } };
+            let next = counter();
+            next(); next(); next()
+        ";
+        assert_eq!("3", eval(Backend::Vm, source));
+        assert_same(source);
+    }
+}
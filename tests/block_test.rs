@@ -9,7 +9,7 @@ mod blocks {
            var x = 10;
            var y = x + 5;
            return y + 5
-         end";
+        ";
 
         let result = rascal::eval(String::from(source));
         assert_eq!("20", result);
@@ -79,7 +79,7 @@ mod blocks {
            y = 1;
            return y
          end";
-        assert_eq!("Value error: imutable y was reassigned.",
+        assert_eq!("Value error: imutable y was reassigned at line 3, column 12",
                    rascal::eval(String::from(source)));
     }
 
@@ -103,7 +103,7 @@ mod blocks {
            x = 1;
            return x
          end";
-        assert_eq!("Value error: variable x used before declared.",
+        assert_eq!("Value error: variable x used before declared at line 3, column 12",
                    rascal::eval(String::from(source)));
     }
 
@@ -117,7 +117,7 @@ mod blocks {
            end;
            return y
          end";
-        assert_eq!("Variable y doesn't exist in this context",
+        assert_eq!("Variable y doesn't exist in this context at line 6, column 19",
                    rascal::eval(String::from(source)));
     }
 
@@ -133,7 +133,7 @@ mod blocks {
            end;
            return x
         ";
-        assert_eq!("Variable z doesn't exist in this context",
+        assert_eq!("Variable z doesn't exist in this context at line 6, column 18",
                    rascal::eval(String::from(source)));
     }
 
@@ -148,7 +148,7 @@ mod blocks {
            end;
            return x
         ";
-        assert_eq!("Value error: variable x has already defined.",
+        assert_eq!("Value error: variable x has already defined at line 4, column 18",
                    rascal::eval(String::from(source)));
     }
 
@@ -161,4 +161,82 @@ mod blocks {
          end";
         rascal::eval(String::from(source));
     }
+
+    #[test]
+    fn it_breaks_out_of_while_blocks() {
+        let source =
+        "
+           var i = 0;
+           var after = 0;
+           while true {
+             i = i + 1;
+             if i == 5 { break };
+             after = after + 1
+           };
+           i * 10 + after
+        ";
+        let result = rascal::eval(String::from(source));
+        assert_eq!("54", result);
+    }
+
+    #[test]
+    fn it_continues_to_the_next_iteration() {
+        let source =
+        "
+           var i = 0;
+           var sum = 0;
+           while i < 10 {
+             i = i + 1;
+             if (i % 2) == 0 { continue };
+             sum = sum + i
+           };
+           sum
+        ";
+        let result = rascal::eval(String::from(source));
+        assert_eq!("25", result);
+    }
+
+    #[test]
+    fn it_breaks_only_the_inner_loop() {
+        let source =
+        "
+           var i = 0;
+           var count = 0;
+           while i < 3 {
+             i = i + 1;
+             var j = 0;
+             while true {
+               j = j + 1;
+               count = count + 1;
+               if j == 2 { break }
+             }
+           };
+           count
+        ";
+        let result = rascal::eval(String::from(source));
+        assert_eq!("6", result);
+    }
+
+    #[test]
+    fn it_validates_break_outside_of_loops() {
+        let source =
+        "
+           var x = 0;
+           continue
+        ";
+        assert_eq!("Flow error: continue outside of a loop at line 3, column 12",
+                   rascal::eval(String::from(source)));
+    }
+
+    #[test]
+    fn it_stops_the_program_on_return() {
+        let source =
+        "
+           var x = 1;
+           { return x + 1 };
+           x = 10
+        ";
+        let result = rascal::eval(String::from(source));
+        assert_eq!("2", result);
+    }
 }
@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod builtins {
+    extern crate rascal;
+
+    #[test]
+    fn it_measures_and_describes_values() {
+        let source =
+        "
+           let sizes = [len(\"rascal\"), len([1, 2]), len({ \"a\": 1 })];
+           let types = [type_of(1), type_of(1.5), type_of(\"a\"), type_of(len), type_of(fn [] { 1 })];
+           [sizes, types]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[[6, 2, 1], [\"Int\", \"Float\", \"Str\", \"Func\", \"Func\"]]", result);
+    }
+
+    #[test]
+    fn it_converts_values() {
+        let source = "[str(12) + \"3\", int(\"42\") + 1, int(2.9), int(true), str([1, \"a\"])]";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[\"123\", 43, 2, 1, \"[1, \\\"a\\\"]\"]", result);
+    }
+
+    #[test]
+    fn it_does_math() {
+        let source = "[abs(-3), abs(2.5 - 4), min(3, 1, 2), max([4, 9.5, 7]), min(\"b\", \"a\")]";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[3, 1.5, 1, 9.5, \"a\"]", result);
+    }
+
+    #[test]
+    fn it_builds_collections() {
+        let source =
+        "
+           var squares = [];
+           for i in range(1, 4) {
+             squares = push(squares, i * i)
+           };
+           [squares, range(3), range(10, 0, -4), keys({ \"b\": 1, \"a\": 2 })]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[[1, 4, 9], [0, 1, 2], [10, 6, 2], [\"a\", \"b\"]]", result);
+    }
+
+    #[test]
+    fn it_pushes_to_a_copy_of_the_list() {
+        let source =
+        "
+           var xs = [];
+           let ys = push(xs, 1);
+           [xs, ys]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[[], [1]]", result);
+    }
+
+    #[test]
+    fn it_asserts_and_panics() {
+        assert_eq!("", rascal::eval(String::from("assert(1 < 2, \"math is broken\")")));
+        assert_eq!("Panic: math is broken at line 1, column 1",
+                   rascal::eval(String::from("assert(2 < 1, \"math is broken\")")));
+        assert_eq!("Panic: assertion failed at line 1, column 1",
+                   rascal::eval(String::from("assert(false)")));
+        assert_eq!("Panic: oh no at line 2, column 1",
+                   rascal::eval(String::from("let x = 1;\npanic(\"oh no\")")));
+    }
+
+    #[test]
+    fn it_validates_arguments() {
+        assert_eq!("Value error: len expects 1 argument but 2 were given at line 1, column 1",
+                   rascal::eval(String::from("len([1], [2])")));
+        assert_eq!("Operation error: unsupported operand type for len: Int at line 1, column 1",
+                   rascal::eval(String::from("len(1)")));
+        assert_eq!("Value error: invalid argument for int: \"abc\" is not an integer \
+                    at line 1, column 1",
+                   rascal::eval(String::from("int(\"abc\")")));
+    }
+
+    #[test]
+    fn it_allows_shadowing_builtins() {
+        let source =
+        "
+           let len = fn [xs] { 0 };
+           let count = fn [xs] { let max = 1; len(xs) + max };
+           count([1, 2, 3])
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("1", result);
+    }
+
+    #[test]
+    fn it_validates_reassigning_builtins() {
+        let source = "len = 1";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Value error: imutable len was reassigned at line 1, column 1", result);
+    }
+}
@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod cli {
+    use std::io::prelude::*;
+    use std::process::{Command, Output, Stdio};
+
+    fn rascal(args: &[&str], stdin: &str) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rascal"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    fn stdout(output: &Output) -> String {
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
+
+    fn stderr(output: &Output) -> String {
+        String::from_utf8_lossy(&output.stderr).to_string()
+    }
+
+    #[test]
+    fn it_runs_files() {
+        for args in [vec!["run", "examples/projecteuler1.rl"], vec!["examples/projecteuler1.rl"]].iter() {
+            let output = rascal(args, "");
+
+            assert!(output.status.success());
+            assert_eq!("23\n", stdout(&output));
+        }
+    }
+
+    #[test]
+    fn it_runs_expressions_and_stdin() {
+        let output = rascal(&["-e", "1 + 1"], "");
+        assert!(output.status.success());
+        assert_eq!("2\n", stdout(&output));
+
+        let output = rascal(&["-"], "print(\"hello\"); let x = 1");
+        assert!(output.status.success());
+        assert_eq!("hello\n", stdout(&output));
+    }
+
+    #[test]
+    fn it_fails_with_errors_on_stderr() {
+        let output = rascal(&["-e", "print(1); 1 / 0"], "");
+        assert_eq!(Some(1), output.status.code());
+        assert_eq!("1\n", stdout(&output));
+        assert!(stderr(&output).contains("--> <expression>:1:11"));
+
+        let output = rascal(&["-e", "let = 1"], "");
+        assert_eq!(Some(1), output.status.code());
+        assert_eq!("", stdout(&output));
+
+        let output = rascal(&["run", "examples/missing.rl"], "");
+        assert_eq!(Some(1), output.status.code());
+        assert!(stderr(&output).starts_with("Can't read examples/missing.rl"));
+    }
+
+    #[test]
+    fn it_gives_scripts_their_arguments_environment_and_exit_status() {
+        let source = "
+            print(args());
+            if type_of(env(\"RASCAL_CLI_TEST\")) == \"Nil\" { exit(2) };
+            exit(3)
+        ";
+
+        let output = rascal(&["-", "a", "b"], source);
+        assert_eq!(Some(2), output.status.code());
+        assert_eq!("[\"a\", \"b\"]\n", stdout(&output));
+        assert_eq!("", stderr(&output));
+    }
+}
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod embedding {
+    extern crate rascal;
+
+    use std::collections::HashMap;
+    use std::convert::TryFrom;
+    use self::rascal::{Interpreter, Value};
+    use self::rascal::error::{Error, RuntimeError};
+
+    #[test]
+    fn it_returns_values() {
+        let mut interpreter = Interpreter::new();
+
+        assert_eq!(Ok(Value::Int(3)), interpreter.eval("1 + 2"));
+        assert_eq!(Ok(Value::from(vec!["a", "b"])), interpreter.eval("[\"a\", \"b\"]"));
+    }
+
+    #[test]
+    fn it_keeps_definitions_between_evaluations() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval("let double = |x| x * 2").unwrap();
+
+        assert_eq!(Ok(Value::Int(8)), interpreter.eval("double(4)"));
+    }
+
+    #[test]
+    fn it_sets_globals() {
+        let mut prices = HashMap::new();
+        prices.insert("apple", 3);
+        prices.insert("pear", 5);
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_global("prices", prices);
+        interpreter.set_global("customer", "ana");
+        interpreter.set_global("vip", true);
+
+        let result = interpreter.eval("
+          let total = prices.apple + prices[\"pear\"];
+          if vip { total - 1 else total }
+        ");
+        assert_eq!(Ok(Value::Int(7)), result);
+        assert_eq!(Ok(Value::from("ana")), interpreter.eval("customer"));
+    }
+
+    #[test]
+    fn it_calls_registered_functions() {
+        let mut interpreter = Interpreter::new();
+        interpreter.register_fn("sum", |args: &[Value]| {
+            let mut total = 0;
+            for arg in args {
+                total += i64::try_from(arg.clone())?;
+            }
+            Ok(Value::from(total))
+        });
+
+        assert_eq!(Ok(Value::Int(6)), interpreter.eval("sum(1, 2, 3)"));
+        assert_eq!(Ok(Value::Int(0)), interpreter.eval("let f = fn [] { sum() }; f()"));
+    }
+
+    #[test]
+    fn it_reports_errors_of_registered_functions() {
+        let mut interpreter = Interpreter::new();
+        interpreter.register_fn("twice", |args: &[Value]| {
+            let text = String::try_from(args[0].clone())?;
+            Ok(Value::from(text.repeat(2)))
+        });
+
+        match interpreter.eval("let x = 1;\ntwice(x)") {
+            Err(Error::Runtime(error @ RuntimeError::Conversion { .. })) =>
+                assert_eq!("Value error: expected Str but found Int at line 2, column 1",
+                           error.to_string()),
+            other => panic!("unexpected result {:?}", other)
+        }
+    }
+
+    #[test]
+    fn it_converts_values_to_rust() {
+        let mut interpreter = Interpreter::new();
+
+        let numbers = interpreter.eval("[1, 2, 3]").unwrap();
+        assert_eq!(Ok(vec![1, 2, 3]), Vec::<i64>::try_from(numbers));
+
+        let user = interpreter.eval("{ \"name\": \"ana\", \"role\": \"admin\" }").unwrap();
+        let user = HashMap::<String, String>::try_from(user).unwrap();
+        assert_eq!("admin", user["role"]);
+
+        assert_eq!(Ok(2.5), f64::try_from(interpreter.eval("5 / 2.0").unwrap()));
+        assert_eq!(Ok(true), bool::try_from(interpreter.eval("1 < 2").unwrap()));
+        assert!(bool::try_from(Value::Int(1)).is_err());
+    }
+
+    #[test]
+    fn it_limits_the_call_depth() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_depth(10);
+        interpreter.eval("let ping = fn [n] { 1 + pong(n + 1) };\nlet pong = fn [n] { 1 + ping(n + 1) }").unwrap();
+
+        match interpreter.eval("ping(0)") {
+            Err(Error::Runtime(RuntimeError::StackOverflow { depth, trace, .. })) => {
+                assert_eq!(10, depth);
+                let names: Vec<&str> = trace.iter().take(3).map(|call| call.0.as_str()).collect();
+                assert_eq!(vec!["ping", "pong", "ping"], names);
+                assert_eq!(11, trace.len());
+            },
+            other => panic!("unexpected result {:?}", other)
+        }
+        assert_eq!(Ok(Value::Int(3)), interpreter.eval("let three = fn [] { 3 };\nthree()"));
+    }
+
+    #[test]
+    fn it_keeps_the_process_away_from_programs_by_default() {
+        let mut interpreter = Interpreter::new();
+
+        for source in ["env(\"HOME\")", "exit(1)"].iter() {
+            match interpreter.eval(source) {
+                Err(Error::Runtime(RuntimeError::UndefinedVariable { .. })) => (),
+                other => panic!("unexpected result {:?}", other)
+            }
+        }
+    }
+
+    #[test]
+    fn it_gives_the_program_arguments_and_its_exit_status() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(Ok(Value::List(vec![])), interpreter.eval("args()"));
+
+        interpreter.set_args(vec![String::from("a"), String::from("b")]);
+        assert_eq!(Ok(Value::Str(String::from("b"))), interpreter.eval("args()[1]"));
+
+        interpreter.allow_process_access();
+        match interpreter.eval("let stop = fn [] { exit(len(args())) };\nstop(); 1") {
+            Err(Error::Runtime(RuntimeError::Exit { code, .. })) => assert_eq!(2, code),
+            other => panic!("unexpected result {:?}", other)
+        }
+    }
+}
@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod errors {
+    extern crate rascal;
+
+    use self::rascal::repl::Repl;
+    use self::rascal::diagnostics::Diagnostic;
+    use self::rascal::error::{Error, RuntimeError};
+
+    #[test]
+    fn it_reports_syntax_errors() {
+        let source =
+        "let x = 1;
+let y = (x + 2;
+y";
+
+        assert_eq!("Sintax error: expected GroupEnd found StatementEnd `;` at line 2, column 15",
+                   rascal::eval(String::from(source)));
+    }
+
+    #[test]
+    fn it_reports_unclosed_blocks() {
+        let source = "while 1 < 2 {";
+
+        assert_eq!("Sintax error: expected End found end of file at line 1, column 14",
+                   rascal::eval(String::from(source)));
+    }
+
+    #[test]
+    fn it_keeps_repl_session_after_syntax_error() {
+        let mut repl = Repl::new();
+
+        repl.eval(String::from("var x = 41"));
+        assert_eq!("Sintax error: expected GroupEnd found end of file at line 1, column 7",
+                   repl.eval(String::from("(x + 1")));
+        assert_eq!("42", repl.eval(String::from("x + 1")));
+    }
+
+    #[test]
+    fn it_reports_runtime_errors_location() {
+        let source =
+        "let add = fn [x] {
+  x + y
+};
+add(1)";
+
+        assert_eq!("Variable y doesn't exist in this context at line 2, column 7",
+                   rascal::eval(String::from(source)));
+    }
+
+    #[test]
+    fn it_reports_not_callable_location() {
+        let source = "let x = 1;\n  x(2)";
+
+        assert_eq!("Value error: x is not callable at line 2, column 3",
+                   rascal::eval(String::from(source)));
+    }
+
+    #[test]
+    fn it_renders_diagnostics_with_help() {
+        let source = "let total = 1;\ntotal = 2";
+        let diagnostic = Diagnostic::from(rascal::run(String::from(source)).unwrap_err());
+
+        assert_eq!("Value error: imutable total was reassigned\n \
+                     --> script.rl:2:1\n  \
+                     |\n\
+                    2 | total = 2\n  \
+                     | ^^^^^\n  \
+                     = help: did you mean `var`? total was declared with `let`, \
+                    which can't be reassigned\n",
+                   diagnostic.render("script.rl", source, false));
+    }
+
+    #[test]
+    fn it_suggests_similar_identifiers() {
+        let source = "var sum = 0;\nsun + 1";
+        let diagnostic = Diagnostic::from(rascal::run(String::from(source)).unwrap_err());
+
+        assert_eq!(Some(String::from("did you mean `sum`?")), diagnostic.help);
+    }
+
+    #[test]
+    fn it_results_in_typed_runtime_errors() {
+        let source = "let x = 1;\nx + \"a\"";
+
+        match rascal::run(String::from(source)) {
+            Err(Error::Runtime(RuntimeError::TypeMismatch { op, left, right, span })) => {
+                assert_eq!(("+", "Int", "Str"), (&*op, &*left, &*right));
+                assert_eq!((2, 1), (span.line, span.column));
+            },
+            other => panic!("unexpected result {:?}", other)
+        }
+    }
+
+    #[test]
+    fn it_reports_division_by_zero() {
+        let source = "let x = 0;\n10 / x";
+
+        assert_eq!("Operation error: division by zero at line 2, column 1",
+                   rascal::eval(String::from(source)));
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn it_reports_integer_overflow() {
+        let source = "var x = 9223372036854775807;\nx = x + 1";
+
+        match rascal::run(String::from(source)) {
+            Err(Error::Runtime(RuntimeError::Overflow { .. })) => (),
+            other => panic!("unexpected result {:?}", other)
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn it_reports_integer_overflow_on_negation() {
+        let source = "let min = -9223372036854775807 - 1;\n-min";
+
+        assert_eq!("Operation error: integer overflow in - at line 2, column 1",
+                   rascal::eval(String::from(source)));
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn it_reports_too_large_integer_literals() {
+        let source = "let x = 1;\nx + 9223372036854775808";
+
+        assert_eq!("Sintax error: integer literal 9223372036854775808 is too large \
+                    at line 2, column 5",
+                   rascal::eval(String::from(source)));
+    }
+
+    #[test]
+    fn it_reports_invalid_comparison() {
+        let source = "true < 1";
+
+        assert_eq!("Operation error: unsupported operand types for <: Bool and Int at line 1, column 1",
+                   rascal::eval(String::from(source)));
+    }
+
+    #[test]
+    fn it_reports_stack_overflow_with_the_call_trace() {
+        let source = "let down = fn [n] { 1 + down(n + 1) };\ndown(0)";
+
+        match rascal::run(String::from(source)) {
+            Err(Error::Runtime(error @ RuntimeError::StackOverflow { .. })) => {
+                assert_eq!("Stack overflow: more than 1000 nested calls at line 1, column 25",
+                           error.to_string());
+                assert_eq!(Some(String::from("call trace, innermost first: \
+                                              down at 1:25 (1000 times) <- down at 2:1")),
+                           error.help());
+            },
+            other => panic!("unexpected result {:?}", other)
+        }
+    }
+}
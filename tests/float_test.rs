@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod floats {
+    extern crate rascal;
+
+    #[test]
+    fn it_eval_float_literals() {
+        let source = "[1.5, 0.25e2, 1e-3, -2.0]";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[1.5, 25.0, 0.001, -2.0]", result);
+    }
+
+    #[test]
+    fn it_promotes_integers_in_mixed_arithmetic() {
+        let source =
+        "
+           let radius = 2;
+           let area = 3.5 * radius * radius;
+           print(7 / 2);
+           area + 1
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("15.0", result);
+    }
+
+    #[test]
+    fn it_compares_floats_and_integers() {
+        let source = "1 == 1.0 and 0.5 < 1 and [2.0] == [2] and 1.5 >= 1.5";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("true", result);
+    }
+
+    #[test]
+    fn it_reads_back_printed_floats() {
+        let source =
+        "
+           let x = 0.1 + 0.2;
+           x == 0.30000000000000004
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("true", result);
+    }
+
+    #[test]
+    fn it_results_in_error_beyond_the_finite_numbers() {
+        let source =
+        "
+           let big = 1e308 * 10.0;
+           big - big
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Operation error: float result of * is not a finite number at line 2, column 22",
+                   result);
+    }
+
+    #[test]
+    fn it_results_in_error_dividing_floats_by_zero() {
+        let source = "1.5 / 0";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Operation error: division by zero at line 1, column 1", result);
+    }
+}
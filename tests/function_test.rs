@@ -32,7 +32,7 @@ mod functions {
         "
            let add = fn [x,y,z] { x + y + z + 1 };
            add(2,2,2)
-         end";
+        ";
 
         let result = rascal::eval(String::from(source));
         assert_eq!("7", result);
@@ -65,8 +65,7 @@ mod functions {
         "
            let foo = fn [x] {
              if x < 10 {
-                x = x + 1;
-                foo(x)
+                x = foo(x + 1)
              };
 
              x
@@ -119,4 +118,430 @@ mod functions {
         let result = rascal::eval(String::from(source));
         assert_eq!("11", result);
     }
+
+    #[test]
+    fn it_results_in_error_for_too_many_arguments() {
+        let source =
+        "
+           let add = fn [x, y] { x + y };
+           add(1, 2, 3)
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Value error: add expects 2 arguments but 3 were given \
+                    at line 3, column 12", result);
+    }
+
+    #[test]
+    fn it_results_in_error_for_missing_arguments() {
+        let source =
+        "
+           let inc = fn [x] { x + 1 };
+           inc()
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Value error: inc expects 1 argument but 0 were given \
+                    at line 3, column 12", result);
+    }
+
+    #[test]
+    fn it_uses_default_params_for_missing_arguments() {
+        let source =
+        "
+           let add = fn [x, y = 1] { x + y };
+           add(1) + add(1, 10)
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("13", result);
+    }
+
+    #[test]
+    fn it_evaluates_defaults_with_previous_params() {
+        let source =
+        "
+           let area = fn [width, height = width] { width * height };
+           area(3)
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("9", result);
+    }
+
+    #[test]
+    fn it_reports_the_accepted_range_of_arguments() {
+        let source =
+        "
+           let add = fn [x, y = 1] { x + y };
+           add(1, 2, 3)
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Value error: add expects 1 to 2 arguments but 3 were given \
+                    at line 3, column 12", result);
+    }
+
+    #[test]
+    fn it_collects_remaining_arguments_in_rest_params() {
+        let source =
+        "
+           let rest = fn [first, ..others] { others };
+           print(rest(1));
+           rest(1, 2, \"three\")
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[2, \"three\"]", result);
+    }
+
+    #[test]
+    fn it_requires_the_params_before_rest() {
+        let source =
+        "
+           let rest = fn [first, ..others] { others };
+           rest()
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Value error: rest expects at least 1 argument but 0 were given \
+                    at line 3, column 12", result);
+    }
+
+    #[test]
+    fn it_returns_closures_that_capture_their_environment() {
+        let source =
+        "
+           let adder = fn [x] {
+             let add = fn [y] { x + y };
+             add
+           };
+
+           let add_two = adder(2);
+           let add_ten = adder(10);
+           add_two(1) + add_ten(1)
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("14", result);
+    }
+
+    #[test]
+    fn it_does_not_see_the_caller_variables() {
+        let source =
+        "
+           let show = fn [] { secret };
+           let caller = fn [] {
+             let secret = 42;
+             show()
+           };
+
+           caller()
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Variable secret doesn't exist in this context at line 2, column 31",
+                   result);
+    }
+
+    #[test]
+    fn it_shadows_outer_variables_with_params() {
+        let source =
+        "
+           let x = 1;
+           let times_ten = fn [x] { x * 10 };
+           times_ten(5) + x
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("51", result);
+    }
+
+    #[test]
+    fn it_shadows_outer_variables_with_locals() {
+        let source =
+        "
+           var x = 1;
+           let foo = fn [] {
+             let x = 2;
+             x
+           };
+
+           foo() + x
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("3", result);
+    }
+
+    #[test]
+    fn it_keeps_state_in_captured_mutable_variables() {
+        let source =
+        "
+           let counter = fn [] {
+             var count = 0;
+             let next = fn [] {
+               count = count + 1;
+               count
+             };
+             next
+           };
+
+           let first = counter();
+           let second = counter();
+           first();
+           first();
+           second();
+           first() * 10 + second()
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("32", result);
+    }
+
+    #[test]
+    fn it_shares_captured_variables_with_the_defining_scope() {
+        let source =
+        "
+           var total = 0;
+           let add = fn [x] { total = total + x };
+           add(2);
+           add(3);
+           total
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("5", result);
+    }
+
+    #[test]
+    fn it_does_not_reassign_captured_immutables() {
+        let source =
+        "
+           let limit = 10;
+           let raise = fn [] { limit = 20 };
+           raise()
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Value error: imutable limit was reassigned at line 3, column 32",
+                   result);
+    }
+
+    #[test]
+    fn it_calls_a_function_from_its_own_body() {
+        let source =
+        "
+           let factorial = fn [n] {
+             if n < 2 { 1 else n * factorial(n - 1) }
+           };
+
+           factorial(5)
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("120", result);
+    }
+
+    #[test]
+    fn it_passes_anonymous_functions_as_args() {
+        let source =
+        "
+           let twice = fn [f, x] { f(f(x)) };
+           twice(fn [x] { x * 3 }, 2)
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("18", result);
+    }
+
+    #[test]
+    fn it_returns_anonymous_functions() {
+        let source =
+        "
+           let adder = fn [x] { fn [y] { x + y } };
+           let add_five = adder(5);
+           add_five(1)
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("6", result);
+    }
+
+    #[test]
+    fn it_stores_anonymous_functions_in_mutable_variables() {
+        let source =
+        "
+           var op = fn [x, y] { x + y };
+           let sum = op(2, 3);
+           op = fn [x, y] { x * y };
+           sum + op(2, 3)
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("11", result);
+    }
+
+    #[test]
+    fn it_eval_arrow_functions() {
+        let source =
+        "
+           let apply = fn [f, x] { f(x) };
+           let offset = 10;
+           let answer = || 42;
+           apply(|x| x + offset, 1) + answer()
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("53", result);
+    }
+
+    #[test]
+    fn it_still_reads_double_pipe_as_or() {
+        let source = "false || true";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("true", result);
+    }
+
+    #[test]
+    fn it_returns_in_the_middle_of_a_function() {
+        let source =
+        "
+           let sign = fn [x] {
+             if x < 0 { return \"negative\" };
+             if x > 0 { return \"positive\" };
+             \"zero\"
+           };
+
+           sign(-1) + \" \" + sign(0) + \" \" + sign(3)
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("negative zero positive", result);
+    }
+
+    #[test]
+    fn it_returns_from_inside_a_loop() {
+        let source =
+        "
+           let first_multiple = fn [of, from] {
+             var x = from;
+             while true {
+               if (x % of) == 0 { return x };
+               x = x + 1
+             };
+             -1
+           };
+
+           first_multiple(7, 30)
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("35", result);
+    }
+
+    #[test]
+    fn it_does_not_run_statements_after_return() {
+        let source =
+        "
+           var calls = 0;
+           let foo = fn [] {
+             return 1;
+             calls = calls + 1
+           };
+
+           foo();
+           calls
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("0", result);
+    }
+
+    #[test]
+    fn it_does_not_break_the_caller_loop() {
+        let source =
+        "
+           let stop = fn [] { break };
+           while true { stop() }
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Flow error: break outside of a loop at line 2, column 31", result);
+    }
+
+    #[test]
+    fn it_calls_mutually_recursive_functions() {
+        let source =
+        "
+           let is_even = fn [n] { if n == 0 { true else is_odd(n - 1) } };
+           let is_odd = fn [n] { if n == 0 { false else is_even(n - 1) } };
+
+           [is_even(10), is_odd(7)]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[true, true]", result);
+    }
+
+    #[test]
+    fn it_calls_mutually_recursive_functions_defined_inside_of_functions() {
+        let source =
+        "
+           let parity = fn [n] {
+             let even = fn [n] { if n == 0 { \"even\" else odd(n - 1) } };
+             let odd = fn [n] { if n == 0 { \"odd\" else even(n - 1) } };
+             even(n)
+           };
+
+           [parity(4), parity(5)]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[\"even\", \"odd\"]", result);
+    }
+
+    #[test]
+    fn it_recurses_deeper_than_the_native_stack_allows() {
+        let source =
+        "
+           let count = fn [n] { if n == 0 { 0 else 1 + count(n - 1) } };
+           count(900)
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("900", result);
+    }
+
+    #[test]
+    fn it_runs_a_million_tail_calls() {
+        let source =
+        "
+           let countdown = fn [n] { if n == 0 { \"done\" else countdown(n - 1) } };
+           countdown(1000000)
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("done", result);
+    }
+
+    #[test]
+    fn it_runs_tail_calls_of_blocks_and_returns() {
+        let source =
+        "
+           let sum = fn [n, total] {
+             if n == 0 { return total };
+             let next = n - 1;
+             sum(next, total + n)
+           };
+           let is_even = fn [n] { if n == 0 { true else is_odd(n - 1) } };
+           let is_odd = fn [n] { if n == 0 { false else return is_even(n - 1) } };
+
+           [sum(100000, 0), is_even(100001)]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[5000050000, false]", result);
+    }
 }
@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod integers {
+    extern crate rascal;
+
+    #[test]
+    fn it_eval_64_bit_integers() {
+        let source =
+        "
+           let big = 3000000000;
+           big * 3 - 9000000000 + 9223372036854775807
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("9223372036854775807", result);
+    }
+
+    #[test]
+    #[cfg(feature = "bigint")]
+    fn it_promotes_to_arbitrary_precision() {
+        let source =
+        "
+           var factorial = 1;
+           var n = 1;
+           while n <= 25 {
+             factorial = factorial * n;
+             n = n + 1
+           };
+           factorial
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("15511210043330985984000000", result);
+    }
+
+    #[test]
+    #[cfg(feature = "bigint")]
+    fn it_demotes_results_that_fit_again() {
+        let source =
+        "
+           let huge = 100000000000000000000;
+           [huge / 10000000000, huge > 1, -(-9223372036854775807 - 1), huge * 0.5]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[10000000000, true, 9223372036854775808, 5e19]", result);
+    }
+}
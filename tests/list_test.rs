@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod lists {
+    extern crate rascal;
+
+    #[test]
+    fn it_eval_list_literals() {
+        let source = "[1, 2 + 3, \"three\", [true], []]";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[1, 5, \"three\", [true], []]", result);
+    }
+
+    #[test]
+    fn it_indexes_lists() {
+        let source =
+        "
+           let xs = [10, 20, [30, 40]];
+           let i = 1;
+           xs[0] + xs[i] + xs[i + 1][1]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("70", result);
+    }
+
+    #[test]
+    fn it_indexes_strings_and_call_results() {
+        let source =
+        "
+           let pair = fn [x] { [x, x * 2] };
+           print(\"rascal\"[1]);
+           pair(4)[1]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("8", result);
+    }
+
+    #[test]
+    fn it_results_in_error_for_out_of_bounds_index() {
+        let source =
+        "
+           let xs = [1, 2, 3];
+           xs[3]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Value error: index 3 is out of bounds for length 3 at line 3, column 12",
+                   result);
+    }
+
+    #[test]
+    fn it_results_in_error_for_non_integer_index() {
+        let source = "[1][true]";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Operation error: unsupported operand types for []: List and Bool \
+                    at line 1, column 1", result);
+    }
+
+    #[test]
+    fn it_concatenates_and_compares_lists() {
+        let source = "[1] + [2, 3] == [1, 2, 3] and [1, 2] != [2, 1]";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("true", result);
+    }
+
+    #[test]
+    fn it_updates_mutable_lists_in_place() {
+        let source =
+        "
+           var grid = [[0, 0], [0, 0]];
+           grid[1][0] = 5;
+           grid[0] = [1];
+           grid
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[[1], [5, 0]]", result);
+    }
+
+    #[test]
+    fn it_validates_immutable_lists() {
+        let source =
+        "
+           let xs = [1, 2];
+           xs[0] = 3
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Value error: imutable xs was reassigned at line 3, column 12", result);
+    }
+
+    #[test]
+    fn it_copies_lists_on_binding() {
+        let source =
+        "
+           var xs = [1, 2];
+           var ys = xs;
+           let keep = xs;
+           ys[0] = 10;
+           xs[1] = 20;
+           [xs, ys, keep]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[[1, 20], [10, 2], [1, 2]]", result);
+    }
+
+    #[test]
+    fn it_updates_lists_captured_by_closures() {
+        let source =
+        "
+           var counts = [0];
+           let count = fn [] { counts[0] = counts[0] + 1 };
+           count();
+           count();
+           counts[0]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("2", result);
+    }
+
+    #[test]
+    fn it_iterates_lists_by_index() {
+        let source =
+        "
+           let xs = [1, 2, 3, 4];
+           var i = 0;
+           var sum = 0;
+           while i < 4 {
+             sum = sum + xs[i];
+             i = i + 1
+           };
+           sum
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("10", result);
+    }
+}
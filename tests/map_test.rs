@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod maps {
+    extern crate rascal;
+
+    #[test]
+    fn it_eval_map_literals() {
+        let source = "let m = { \"b\": [1, 2], \"a\": { \"c\": \"d\" } }; m";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("{\"a\": {\"c\": \"d\"}, \"b\": [1, 2]}", result);
+    }
+
+    #[test]
+    fn it_tells_maps_apart_from_blocks() {
+        let source =
+        "
+           var x = 1;
+           { x = x + 1 };
+           { \"x\": x }
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("{\"x\": 2}", result);
+    }
+
+    #[test]
+    fn it_looks_up_keys() {
+        let source =
+        "
+           let config = { \"name\": \"rascal\", \"server\": { \"port\": 8080 } };
+           let key = \"name\";
+           print(config[key]);
+           config.server.port + config[\"server\"][\"port\"]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("16160", result);
+    }
+
+    #[test]
+    fn it_results_in_error_for_missing_keys() {
+        let source =
+        "
+           let config = { \"name\": \"rascal\" };
+           config.nmae
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Value error: key \"nmae\" not found at line 3, column 12", result);
+    }
+
+    #[test]
+    fn it_compares_maps_structurally() {
+        let source =
+        "
+           let a = { \"x\": 1, \"y\": [2] };
+           let b = { \"y\": [2], \"x\": 1 };
+           a == b and a != { \"x\": 1 }
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("true", result);
+    }
+
+    #[test]
+    fn it_updates_mutable_maps() {
+        let source =
+        "
+           var user = { \"name\": \"ana\", \"tags\": {} };
+           user.name = \"bia\";
+           user[\"age\"] = 30;
+           user.tags.admin = true;
+           user
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("{\"age\": 30, \"name\": \"bia\", \"tags\": {\"admin\": true}}", result);
+    }
+
+    #[test]
+    fn it_validates_immutable_maps() {
+        let source =
+        "
+           let user = { \"name\": \"ana\" };
+           user.name = \"bia\"
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Value error: imutable user was reassigned at line 3, column 12", result);
+    }
+
+    #[test]
+    fn it_iterates_map_keys() {
+        let source =
+        "
+           let ages = { \"bia\": 30, \"ana\": 20, \"caio\": 5 };
+           var names = \"\";
+           var total = 0;
+           for name in ages {
+             names = names + name + \" \";
+             total = total + ages[name]
+           };
+           print(names);
+           total
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("55", result);
+    }
+
+    #[test]
+    fn it_iterates_lists_and_strings() {
+        let source =
+        "
+           var letters = [];
+           for letter in \"abc\" {
+             letters = [letter] + letters
+           };
+           var sum = 0;
+           for x in [1, 2, 3, 4] {
+             if x == 4 { break };
+             if x == 2 { continue };
+             sum = sum + x
+           };
+           [letters, sum]
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("[[\"c\", \"b\", \"a\"], 4]", result);
+    }
+
+    #[test]
+    fn it_validates_the_loop_variable() {
+        let source =
+        "
+           let x = 1;
+           for x in [1] { x }
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("Value error: variable x has already defined at line 3, column 16",
+                   result);
+    }
+}
@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod strings {
+    extern crate rascal;
+
+    #[test]
+    fn it_eval_string_literals() {
+        let source =
+        "
+           let greeting = \"hello\";
+           greeting
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("hello", result);
+    }
+
+    #[test]
+    fn it_eval_string_escapes() {
+        let source = r#""say \"hi\"\tto \u{1F980}""#;
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("say \"hi\"\tto \u{1F980}", result);
+    }
+
+    #[test]
+    fn it_concatenates_strings() {
+        let source =
+        "
+           var message = \"foo\";
+           message = message + \" \" + \"bar\";
+           message
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("foo bar", result);
+    }
+
+    #[test]
+    fn it_compares_strings() {
+        let source =
+        "
+           let name = \"rascal\";
+           if name == \"rascal\" and \"a\" < \"b\" {
+             \"equal\"
+           else
+             \"different\"
+           }
+        ";
+
+        let result = rascal::eval(String::from(source));
+        assert_eq!("equal", result);
+    }
+
+    #[test]
+    fn it_can_print_strings() {
+        let source = "print(\"hello world\")";
+        assert_eq!("", rascal::eval(String::from(source)));
+    }
+}